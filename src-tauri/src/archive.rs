@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::batch::{self, BatchProgress, BatchResult, Destination};
+use crate::cache::DetectionCache;
+use crate::config::AppConfig;
+
+/// Convert `file_paths` in memory and pack them into a ZIP at `zip_path`, one entry
+/// per file under its original base name. Binary and already-UTF-8 files are stored
+/// as-is; skipped and failed files are left out. Each packed result's `converted_path`
+/// is its entry name. Repeats of the same file are packed once. A cancelled batch still
+/// produces an archive of what finished.
+pub fn batch_to_zip<F>(
+    file_paths: &[String],
+    cfg: &AppConfig,
+    zip_path: &Path,
+    cache: &DetectionCache,
+    cancel: &AtomicBool,
+    on_progress: F,
+) -> Result<BatchResult, String>
+where
+    F: Fn(BatchProgress) + Sync,
+{
+    let (unique_paths, duplicates_skipped) = batch::dedupe_paths(file_paths);
+    let items = batch::process_all(
+        &unique_paths,
+        cfg,
+        &Destination::Memory,
+        cache,
+        cancel,
+        on_progress,
+    );
+
+    let file = File::create(zip_path).map_err(|e| format!("Failed to create zip file: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut used_names = HashSet::new();
+
+    let mut results = Vec::with_capacity(items.len());
+    for item in items {
+        let mut result = item.result;
+        let data = match (result.status.as_str(), item.data) {
+            ("converted", Some(data)) => data,
+            ("binary" | "already_utf8", _) => match fs::read(&result.file_path) {
+                Ok(data) => data,
+                Err(e) => {
+                    result.status = "error".to_string();
+                    result.error_message = Some(format!("Failed to read file: {}", e));
+                    results.push(result);
+                    continue;
+                }
+            },
+            _ => {
+                results.push(result);
+                continue;
+            }
+        };
+
+        let entry_name = unique_entry_name(&result.file_name, &mut used_names);
+        zip.start_file(entry_name.as_str(), options)
+            .map_err(|e| format!("Failed to add {} to zip: {}", entry_name, e))?;
+        zip.write_all(&data)
+            .map_err(|e| format!("Failed to write {} to zip: {}", entry_name, e))?;
+        result.converted_path = Some(entry_name);
+        results.push(result);
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish zip file: {}", e))?;
+    let mut result = BatchResult::from_results(file_paths.len(), results);
+    result.duplicates_skipped = duplicates_skipped;
+    Ok(result)
+}
+
+/// `name`, or `stem_1.ext`, `stem_2.ext`, ... if an earlier entry already took it.
+/// Names are compared case-insensitively so the archive also extracts cleanly on Windows.
+fn unique_entry_name(name: &str, used: &mut HashSet<String>) -> String {
+    if used.insert(name.to_lowercase()) {
+        return name.to_string();
+    }
+
+    let path = Path::new(name);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(name);
+    let ext = path.extension().and_then(|e| e.to_str());
+    (1..)
+        .map(|index| match ext {
+            Some(ext) => format!("{}_{}.{}", stem, index, ext),
+            None => format!("{}_{}", stem, index),
+        })
+        .find(|candidate| used.insert(candidate.to_lowercase()))
+        .expect("unbounded index range always finds a free name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("encodingman_archive_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_file(path: PathBuf, data: &[u8]) -> String {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, data).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Entry names and contents of the archive at `zip_path`, in order.
+    fn read_entries(zip_path: &Path) -> Vec<(String, Vec<u8>)> {
+        let mut archive = zip::ZipArchive::new(File::open(zip_path).unwrap()).unwrap();
+        (0..archive.len())
+            .map(|i| {
+                let mut entry = archive.by_index(i).unwrap();
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).unwrap();
+                (entry.name().to_string(), data)
+            })
+            .collect()
+    }
+
+    fn zip_files(file_paths: &[String], cfg: &AppConfig, zip_path: &Path) -> BatchResult {
+        let cache = DetectionCache::default();
+        batch_to_zip(
+            file_paths,
+            cfg,
+            zip_path,
+            &cache,
+            &AtomicBool::new(false),
+            |_| {},
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn same_named_inputs_get_distinct_entries() {
+        let dir = test_dir("same_names");
+        let file_paths = [
+            write_file(dir.join("a").join("data.csv"), b"a,1\n"),
+            write_file(dir.join("b").join("data.csv"), b"b,2\n"),
+            write_file(dir.join("c").join("DATA.csv"), b"c,3\n"),
+        ];
+        let zip_path = dir.join("out.zip");
+
+        let result = zip_files(&file_paths, &AppConfig::default(), &zip_path);
+
+        let names: Vec<_> = read_entries(&zip_path)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["data.csv", "data_1.csv", "DATA_2.csv"]);
+        let converted: Vec<_> = result
+            .results
+            .iter()
+            .map(|r| r.converted_path.as_deref().unwrap())
+            .collect();
+        assert_eq!(converted, names);
+    }
+
+    #[test]
+    fn zip_entries_follow_bom_settings() {
+        let dir = test_dir("bom");
+        let sjis = encoding_rs::SHIFT_JIS
+            .encode("これはShift_JISで保存されたテキストです。\r\n日本語の文章が続きます。\r\n")
+            .0
+            .into_owned();
+        let file_paths = [
+            write_file(dir.join("sjis.txt"), &sjis),
+            write_file(dir.join("bom.txt"), "\u{FEFF}UTF-8 with BOM\n".as_bytes()),
+        ];
+
+        let with_bom = dir.join("with_bom.zip");
+        zip_files(&file_paths, &AppConfig::default(), &with_bom);
+        let entries = read_entries(&with_bom);
+        assert!(entries[0].1.starts_with(b"\xEF\xBB\xBF"));
+        assert!(entries[1].1.starts_with(b"\xEF\xBB\xBF"));
+
+        let cfg = AppConfig {
+            target_encoding: "utf-8".to_string(),
+            ..AppConfig::default()
+        };
+        let without_bom = dir.join("without_bom.zip");
+        zip_files(&file_paths, &cfg, &without_bom);
+        let entries = read_entries(&without_bom);
+        assert!(!entries[0].1.starts_with(b"\xEF\xBB\xBF"));
+        // Already UTF-8, so stored as-is
+        assert_eq!(entries[1].1, "\u{FEFF}UTF-8 with BOM\n".as_bytes());
+
+        let cfg = AppConfig {
+            strip_existing_bom: true,
+            ..cfg
+        };
+        let stripped = dir.join("stripped.zip");
+        zip_files(&file_paths, &cfg, &stripped);
+        let entries = read_entries(&stripped);
+        assert_eq!(entries[1].1, b"UTF-8 with BOM\n");
+    }
+
+    #[test]
+    fn repeated_inputs_are_packed_once_but_counted() {
+        let dir = test_dir("repeated_inputs");
+        let file = write_file(dir.join("data.csv"), b"a,1\n");
+        let file_paths = [file.clone(), file];
+        let zip_path = dir.join("out.zip");
+
+        let result = zip_files(&file_paths, &AppConfig::default(), &zip_path);
+
+        assert_eq!(result.total, 2);
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.duplicates_skipped, 1);
+        assert_eq!(read_entries(&zip_path).len(), 1);
+    }
+}
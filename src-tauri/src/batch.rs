@@ -0,0 +1,852 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+use crate::cache::DetectionCache;
+use crate::config::AppConfig;
+use crate::encoder::DetectionResult;
+use crate::{csv, encoder, launcher};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchFileResult {
+    pub file_path: String,
+    pub file_name: String,
+    pub status: String, // "converted", "already_utf8", "binary", "skipped", "error"
+    pub detected_encoding: Option<String>,
+    pub error_message: Option<String>,
+    pub replacement_count: usize,
+    pub lossy: bool,
+    /// Where the converted copy was written ("converted" entries only).
+    pub converted_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub results: Vec<BatchFileResult>,
+    /// Number of paths requested, including duplicates and files a cancel left
+    /// unprocessed, so it can exceed `results.len()`.
+    pub total: usize,
+    pub converted: usize,
+    pub already_utf8: usize,
+    pub binary: usize,
+    pub skipped: usize,
+    pub errors: usize,
+    /// True when the batch was stopped early via `cancel_batch`.
+    pub cancelled: bool,
+    /// Input paths dropped because they named a file already in the batch.
+    #[serde(default)]
+    pub duplicates_skipped: usize,
+}
+
+/// Cancellation flag for the running batch, stored in Tauri state.
+#[derive(Debug, Default)]
+pub struct BatchCancel(pub AtomicBool);
+
+impl BatchResult {
+    /// Build the summary of a batch of `total` requested paths by tallying each
+    /// result's status.
+    pub fn from_results(total: usize, results: Vec<BatchFileResult>) -> Self {
+        let count = |status: &str| results.iter().filter(|r| r.status == status).count();
+        Self {
+            total,
+            converted: count("converted"),
+            already_utf8: count("already_utf8"),
+            binary: count("binary"),
+            skipped: count("skipped"),
+            errors: count("error"),
+            cancelled: false,
+            duplicates_skipped: 0,
+            results,
+        }
+    }
+}
+
+/// `file_paths` with repeats of the same file removed, keeping the first occurrence.
+/// Paths are compared canonicalized, so relative paths and symlinks to one target
+/// count once; paths that can't be resolved (e.g. missing files) are compared as given.
+/// Returns the kept paths and how many were dropped.
+pub fn dedupe_paths(file_paths: &[String]) -> (Vec<String>, usize) {
+    let mut seen = HashSet::new();
+    let unique: Vec<String> = file_paths
+        .iter()
+        .filter(|path| {
+            let path = Path::new(path.as_str());
+            seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()))
+        })
+        .cloned()
+        .collect();
+    let duplicates = file_paths.len() - unique.len();
+    (unique, duplicates)
+}
+
+/// Columns of the CSV batch report, in order.
+const REPORT_COLUMNS: [&str; 5] = [
+    "file_path",
+    "file_name",
+    "status",
+    "detected_encoding",
+    "error_message",
+];
+
+/// Render per-file results as a report: "csv" (one row per file, CRLF line breaks)
+/// or "json" (the results array as serialized for the UI).
+pub fn batch_report(results: &[BatchFileResult], format: &str) -> Result<String, String> {
+    match format.trim().to_ascii_lowercase().as_str() {
+        "json" => serde_json::to_string_pretty(results)
+            .map_err(|e| format!("Failed to serialize report: {}", e)),
+        "csv" => {
+            let mut out = REPORT_COLUMNS.join(",");
+            out.push_str("\r\n");
+            for result in results {
+                let fields = [
+                    result.file_path.as_str(),
+                    result.file_name.as_str(),
+                    result.status.as_str(),
+                    result.detected_encoding.as_deref().unwrap_or(""),
+                    result.error_message.as_deref().unwrap_or(""),
+                ];
+                let row: Vec<String> = fields.iter().map(|f| csv::quote_field(f)).collect();
+                out.push_str(&row.join(","));
+                out.push_str("\r\n");
+            }
+            Ok(out)
+        }
+        other => Err(format!("Unknown report format: {}", other)),
+    }
+}
+
+/// Progress payload reported after each file finishes.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchProgress {
+    /// Position of the file in the input list (files may finish out of order).
+    pub index: usize,
+    pub total: usize,
+    pub file_name: String,
+    pub status: String,
+}
+
+/// Where converted files are written.
+#[derive(Debug, Clone)]
+pub enum Destination {
+    /// Nowhere: detect only and report the would-be status.
+    DryRun,
+    /// Flat into the given temp directory (the default for opening files).
+    Temp(PathBuf),
+    /// Nowhere: the converted bytes are returned in `BatchItem::data`.
+    Memory,
+    /// Over the original file, after an optional `.bak` copy.
+    InPlace { backup: bool },
+    /// Mirrored under `output_dir`, keeping each file's path relative to `source_root`.
+    Mirror {
+        source_root: PathBuf,
+        output_dir: PathBuf,
+    },
+}
+
+/// Which of a batch's files get opened once it finishes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchLaunchMode {
+    /// Every file (one editor window per file).
+    All,
+    /// Nothing; the files are only converted.
+    None,
+    /// Only the first file that would otherwise be opened.
+    FirstOnly,
+}
+
+impl BatchLaunchMode {
+    /// Parse a config value ("all", "none", "first_only"). Unknown values fall back to All.
+    pub fn from_label(label: &str) -> Self {
+        match label.to_ascii_lowercase().as_str() {
+            "none" => BatchLaunchMode::None,
+            "first_only" => BatchLaunchMode::FirstOnly,
+            _ => BatchLaunchMode::All,
+        }
+    }
+}
+
+/// One processed batch entry plus the file to open afterwards, if any.
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    pub result: BatchFileResult,
+    pub launch_path: Option<String>,
+    /// The converted bytes (`Destination::Memory` "converted" entries only).
+    pub data: Option<Vec<u8>>,
+}
+
+/// Read, detect, convert, and write a single file. Never launches anything,
+/// so it is safe to run from worker threads.
+/// With `Destination::DryRun`, stops after detection and reports the would-be status
+/// without converting or writing anything.
+/// Detections are looked up in and added to `cache`; a dry run of an unchanged file
+/// that was detected before doesn't read it at all.
+pub fn process_file(
+    file_path: &str,
+    cfg: &AppConfig,
+    destination: &Destination,
+    cache: &DetectionCache,
+) -> BatchItem {
+    let path = Path::new(file_path);
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let base = BatchFileResult {
+        file_path: file_path.to_string(),
+        file_name,
+        status: "error".to_string(),
+        detected_encoding: None,
+        error_message: None,
+        replacement_count: 0,
+        lossy: false,
+        converted_path: None,
+    };
+
+    let dry_run = matches!(destination, Destination::DryRun);
+    // Nothing is written for dry runs and in-memory batches, so there is nothing to open
+    let opens_files = !matches!(destination, Destination::DryRun | Destination::Memory);
+    let launch = |target: &str| opens_files.then(|| target.to_string());
+
+    if !path.exists() {
+        return BatchItem {
+            result: BatchFileResult {
+                error_message: Some("File not found".to_string()),
+                ..base
+            },
+            launch_path: None,
+            data: None,
+        };
+    }
+
+    // Binary passthrough
+    if encoder::is_binary_file(path) {
+        return BatchItem {
+            result: BatchFileResult {
+                status: "binary".to_string(),
+                ..base
+            },
+            launch_path: launch(file_path),
+            data: None,
+        };
+    }
+
+    // Size guard before reading the whole file into memory
+    if let Some(limit) = cfg.max_file_size_bytes {
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if size > limit {
+            return BatchItem {
+                result: BatchFileResult {
+                    status: "skipped".to_string(),
+                    error_message: Some(format!(
+                        "File too large: {} bytes (limit {} bytes)",
+                        size, limit
+                    )),
+                    ..base
+                },
+                launch_path: None,
+                data: None,
+            };
+        }
+    }
+
+    // Only files that needed scoring are cached, so a hit is never UTF-8
+    let lookup = cache.lookup(path);
+    if dry_run {
+        if let Some(detection) = lookup.cached().cloned() {
+            return BatchItem {
+                result: BatchFileResult {
+                    status: "converted".to_string(),
+                    detected_encoding: Some(detection.encoding_name),
+                    ..base
+                },
+                launch_path: None,
+                data: None,
+            };
+        }
+    }
+
+    let data = match encoder::read_file_bytes(path) {
+        Ok(data) => data,
+        Err(e) => {
+            return BatchItem {
+                result: BatchFileResult {
+                    error_message: Some(format!("{}", e)),
+                    ..base
+                },
+                launch_path: None,
+                data: None,
+            };
+        }
+    };
+
+    let options = cfg.convert_options();
+    let already_utf8 = encoder::is_already_utf8(&data);
+    if already_utf8 && !options.strips_bom(&data) {
+        return BatchItem {
+            result: BatchFileResult {
+                status: "already_utf8".to_string(),
+                detected_encoding: Some(encoder::utf8_display_name(&data).to_string()),
+                ..base
+            },
+            launch_path: launch(file_path),
+            data: None,
+        };
+    }
+
+    let detection = if already_utf8 {
+        // Only the BOM needs to go
+        DetectionResult {
+            encoding_name: "UTF-8".to_string(),
+            confidence: 1.0,
+        }
+    } else {
+        cache.detect(path, lookup, &data, cfg)
+    };
+    let base = BatchFileResult {
+        detected_encoding: Some(detection.encoding_name.clone()),
+        ..base
+    };
+
+    if dry_run {
+        return BatchItem {
+            result: BatchFileResult {
+                status: "converted".to_string(),
+                ..base
+            },
+            launch_path: None,
+            data: None,
+        };
+    }
+
+    let converted = match encoder::convert_with_options(&data, &detection.encoding_name, &options) {
+        Ok(converted) => converted,
+        Err(e) => {
+            return BatchItem {
+                result: BatchFileResult {
+                    error_message: Some(e),
+                    ..base
+                },
+                launch_path: None,
+                data: None,
+            };
+        }
+    };
+
+    log::info!(
+        "detected {}: {} (confidence {:.2}, {} replacements)",
+        file_path,
+        detection.encoding_name,
+        detection.confidence,
+        converted.replacement_count
+    );
+
+    let base = BatchFileResult {
+        replacement_count: converted.replacement_count,
+        lossy: converted.lossy,
+        ..base
+    };
+
+    if matches!(destination, Destination::Memory) {
+        return BatchItem {
+            result: BatchFileResult {
+                status: "converted".to_string(),
+                ..base
+            },
+            launch_path: None,
+            data: Some(converted.data),
+        };
+    }
+
+    let written = match destination {
+        Destination::DryRun | Destination::Memory => {
+            unreachable!("dry runs and in-memory batches return before writing")
+        }
+        Destination::Temp(temp_dir) => {
+            let modified = launcher::modified_time(path);
+            launcher::create_temp_file(
+                temp_dir,
+                path,
+                &detection.encoding_name,
+                &options,
+                &converted.data,
+                modified,
+                cfg.reuse_temp,
+            )
+        }
+        Destination::InPlace { backup } => {
+            launcher::overwrite_in_place(path, &converted.data, *backup)
+        }
+        Destination::Mirror {
+            source_root,
+            output_dir,
+        } => launcher::mirrored_output_path(source_root, path, output_dir)
+            .and_then(|target| launcher::write_output_file(&target, &converted.data)),
+    };
+
+    match written {
+        Ok(output_path) => BatchItem {
+            result: BatchFileResult {
+                status: "converted".to_string(),
+                converted_path: Some(output_path.clone()),
+                ..base
+            },
+            launch_path: Some(output_path),
+            data: None,
+        },
+        Err(e) => BatchItem {
+            result: BatchFileResult {
+                error_message: Some(e),
+                ..base
+            },
+            launch_path: None,
+            data: None,
+        },
+    }
+}
+
+/// Open the finished items' files one after another, as many as `mode` allows.
+/// `launch` gets the original file path and the path to open; its errors are
+/// ignored so one failed launch doesn't stop the rest. Returns the number of launches.
+pub fn launch_items<F>(items: &[BatchItem], mode: BatchLaunchMode, mut launch: F) -> usize
+where
+    F: FnMut(&str, &str) -> Result<(), String>,
+{
+    let limit = match mode {
+        BatchLaunchMode::All => usize::MAX,
+        BatchLaunchMode::None => 0,
+        BatchLaunchMode::FirstOnly => 1,
+    };
+    let targets = items
+        .iter()
+        .filter_map(|item| Some((item.result.file_path.as_str(), item.launch_path.as_deref()?)))
+        .take(limit);
+
+    let mut launched = 0;
+    for (file_path, launch_path) in targets {
+        let _ = launch(file_path, launch_path);
+        launched += 1;
+    }
+    launched
+}
+
+/// Process every file across a pool of worker threads, calling `on_progress` as each
+/// one finishes. Workers pull the next index from a shared counter, so slow files
+/// don't stall a whole chunk, and results are returned in input order.
+/// Once `cancel` is set, no new files are started; files already in flight finish,
+/// so the returned items are always a prefix of the input.
+pub fn process_all<F>(
+    file_paths: &[String],
+    cfg: &AppConfig,
+    destination: &Destination,
+    cache: &DetectionCache,
+    cancel: &AtomicBool,
+    on_progress: F,
+) -> Vec<BatchItem>
+where
+    F: Fn(BatchProgress) + Sync,
+{
+    let total = file_paths.len();
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(file_paths.len())
+        .max(1);
+    let next = AtomicUsize::new(0);
+
+    let mut indexed: Vec<(usize, BatchItem)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    while !cancel.load(Ordering::Relaxed) {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(file_path) = file_paths.get(index) else {
+                            break;
+                        };
+                        let item = process_file(file_path, cfg, destination, cache);
+                        on_progress(BatchProgress {
+                            index,
+                            total,
+                            file_name: item.result.file_name.clone(),
+                            status: item.result.status.clone(),
+                        });
+                        done.push((index, item));
+                    }
+                    done
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("batch worker panicked"))
+            .collect()
+    });
+
+    indexed.sort_by_key(|(index, _)| *index);
+    indexed.into_iter().map(|(_, item)| item).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("encodingman_batch_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn many_files_keep_input_order_and_counts() {
+        let dir = test_dir("many");
+        let sjis = encoding_rs::SHIFT_JIS.encode("日本語のテキストです。").0;
+        let mut paths = Vec::new();
+        for i in 0..40 {
+            let path = dir.join(format!("file{:02}.txt", i));
+            match i % 4 {
+                0 => std::fs::write(&path, &sjis[..]).unwrap(),
+                1 => std::fs::write(&path, "plain ascii").unwrap(),
+                2 => std::fs::write(&path, b"%PDF-1.7 binary").unwrap(),
+                _ => {} // left missing
+            }
+            paths.push(path.to_string_lossy().to_string());
+        }
+
+        let temp_dir = dir.join("temp");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let items = process_all(
+            &paths,
+            &AppConfig::default(),
+            &Destination::Temp(temp_dir),
+            &DetectionCache::default(),
+            &AtomicBool::new(false),
+            |_| {},
+        );
+        let result_paths: Vec<_> = items.iter().map(|i| i.result.file_path.clone()).collect();
+        assert_eq!(result_paths, paths);
+
+        let results = items.into_iter().map(|i| i.result).collect();
+        let summary = BatchResult::from_results(paths.len(), results);
+        assert_eq!(summary.total, 40);
+        assert_eq!(summary.converted, 10);
+        assert_eq!(summary.already_utf8, 10);
+        assert_eq!(summary.binary, 10);
+        assert_eq!(summary.errors, 10);
+    }
+
+    #[test]
+    fn strict_mode_reports_error_status() {
+        let dir = test_dir("strict");
+        let path = dir.join("broken.txt");
+        std::fs::write(&path, b"\x82\xA0 abc \x82").unwrap();
+        // Only UTF-8 is tried, so the best candidate cannot decode the file cleanly
+        let cfg = AppConfig {
+            lossy_allowed: false,
+            candidate_encodings: vec!["UTF-8".to_string()],
+            ..AppConfig::default()
+        };
+        let item = process_file(
+            &path.to_string_lossy(),
+            &cfg,
+            &Destination::Temp(dir.join("temp")),
+            &DetectionCache::default(),
+        );
+        assert_eq!(item.result.status, "error");
+        assert!(item.result.error_message.is_some());
+        assert!(item.launch_path.is_none());
+    }
+
+    #[test]
+    fn progress_is_reported_once_per_file() {
+        let dir = test_dir("progress");
+        let mut paths = Vec::new();
+        for i in 0..10 {
+            let path = dir.join(format!("p{}.txt", i));
+            std::fs::write(&path, "ascii").unwrap();
+            paths.push(path.to_string_lossy().to_string());
+        }
+
+        let seen = std::sync::Mutex::new(Vec::new());
+        process_all(
+            &paths,
+            &AppConfig::default(),
+            &Destination::DryRun,
+            &DetectionCache::default(),
+            &AtomicBool::new(false),
+            |progress| {
+                assert_eq!(progress.total, 10);
+                assert_eq!(progress.status, "already_utf8");
+                seen.lock().unwrap().push(progress.index);
+            },
+        );
+
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort();
+        assert_eq!(seen, (0..10).collect::<Vec<_>>());
+    }
+
+    /// Write `count` Shift_JIS files into `dir` and return their paths.
+    fn write_shift_jis_files(dir: &Path, count: usize) -> Vec<String> {
+        let text = "これはShift_JISで保存されたテキストです。\r\n日本語の文章が続きます。\r\n";
+        let data = encoding_rs::SHIFT_JIS.encode(text).0.into_owned();
+        (0..count)
+            .map(|i| {
+                let file = dir.join(format!("file{}.txt", i));
+                std::fs::write(&file, &data).unwrap();
+                file.to_string_lossy().into_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cancel_stops_batch_early() {
+        let dir = test_dir("cancel");
+        let workers = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        let file_paths = write_shift_jis_files(&dir, workers * 4 + 8);
+        let cancel = AtomicBool::new(false);
+        let finished = AtomicUsize::new(0);
+        let temp_dir = dir.join("temp");
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let items = process_all(
+            &file_paths,
+            &AppConfig::default(),
+            &Destination::Temp(temp_dir),
+            &DetectionCache::default(),
+            &cancel,
+            |_| {
+                if finished.fetch_add(1, Ordering::Relaxed) + 1 == 2 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            },
+        );
+
+        assert!(cancel.load(Ordering::Relaxed));
+        assert!(items.len() >= 2);
+        assert!(items.len() < file_paths.len());
+        // Always a prefix of the input
+        for (item, file_path) in items.iter().zip(&file_paths) {
+            assert_eq!(&item.result.file_path, file_path);
+        }
+
+        let results = items.into_iter().map(|i| i.result).collect();
+        assert_eq!(
+            BatchResult::from_results(file_paths.len(), results).total,
+            file_paths.len()
+        );
+    }
+
+    #[test]
+    fn dry_run_writes_nothing() {
+        let dir = test_dir("dry_run");
+        let file = dir.join("dry_run_only.txt");
+        let data = encoding_rs::SHIFT_JIS.encode("日本語のテキストです。").0;
+        std::fs::write(&file, &data[..]).unwrap();
+        let file_paths = vec![file.to_string_lossy().into_owned()];
+
+        let items = process_all(
+            &file_paths,
+            &AppConfig::default(),
+            &Destination::DryRun,
+            &DetectionCache::default(),
+            &AtomicBool::new(false),
+            |_| {},
+        );
+
+        assert_eq!(items[0].result.status, "converted");
+        assert_eq!(
+            items[0].result.detected_encoding.as_deref(),
+            Some("Shift_JIS")
+        );
+        assert_eq!(items[0].launch_path, None);
+        // Only the source file; no converted copy was written next to it or elsewhere
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn oversized_file_is_skipped() {
+        let dir = test_dir("max_file_size");
+        let under = dir.join("under.csv");
+        let over = dir.join("over.csv");
+        fs::write(&under, "0123456789").unwrap();
+        fs::write(&over, "01234567890").unwrap();
+        let cfg = AppConfig {
+            max_file_size_bytes: Some(10),
+            ..AppConfig::default()
+        };
+
+        let results: Vec<BatchFileResult> = [&under, &over]
+            .iter()
+            .map(|path| {
+                let path = path.to_str().unwrap();
+                process_file(
+                    path,
+                    &cfg,
+                    &Destination::Temp(dir.join("temp")),
+                    &DetectionCache::default(),
+                )
+                .result
+            })
+            .collect();
+        assert_eq!(results[0].status, "already_utf8");
+        assert_eq!(results[1].status, "skipped");
+        assert!(results[1]
+            .error_message
+            .as_deref()
+            .unwrap()
+            .contains("limit 10 bytes"));
+
+        let summary = BatchResult::from_results(2, results);
+        assert_eq!(summary.skipped, 1);
+        assert_eq!(summary.already_utf8, 1);
+    }
+
+    #[test]
+    fn converted_entries_report_their_output_path() {
+        let dir = test_dir("converted_path");
+        let temp_dir = dir.join("temp");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let sjis = dir.join("to_convert.csv");
+        fs::write(
+            &sjis,
+            &encoding_rs::SHIFT_JIS.encode("日本語のテキストです。").0[..],
+        )
+        .unwrap();
+        let utf8 = dir.join("utf8.csv");
+        fs::write(&utf8, "ascii").unwrap();
+        let binary = dir.join("image.png");
+        fs::write(&binary, b"\x89PNG\r\n\x1a\n").unwrap();
+
+        let destination = Destination::Temp(temp_dir.clone());
+        let process = |path: &Path| {
+            process_file(
+                path.to_str().unwrap(),
+                &AppConfig::default(),
+                &destination,
+                &DetectionCache::default(),
+            )
+            .result
+        };
+
+        let converted = process(&sjis);
+        let output = converted
+            .converted_path
+            .expect("converted entries have a path");
+        assert!(Path::new(&output).starts_with(&temp_dir));
+        assert!(Path::new(&output).exists());
+        assert_eq!(process(&utf8).converted_path, None);
+        assert_eq!(process(&binary).converted_path, None);
+    }
+
+    #[test]
+    fn report_lists_each_file_as_csv_or_json() {
+        let results = vec![
+            BatchFileResult {
+                file_path: "/data/a.csv".to_string(),
+                file_name: "a.csv".to_string(),
+                status: "converted".to_string(),
+                detected_encoding: Some("Shift_JIS".to_string()),
+                error_message: None,
+                replacement_count: 0,
+                lossy: false,
+                converted_path: None,
+            },
+            BatchFileResult {
+                file_path: "/data/b.txt".to_string(),
+                file_name: "b.txt".to_string(),
+                status: "error".to_string(),
+                detected_encoding: None,
+                error_message: Some("bad \"bytes\", sorry".to_string()),
+                replacement_count: 0,
+                lossy: false,
+                converted_path: None,
+            },
+        ];
+
+        let csv = batch_report(&results, "CSV").unwrap();
+        assert_eq!(
+            csv,
+            "file_path,file_name,status,detected_encoding,error_message\r\n\
+             /data/a.csv,a.csv,converted,Shift_JIS,\r\n\
+             /data/b.txt,b.txt,error,,\"bad \"\"bytes\"\", sorry\"\r\n"
+        );
+
+        let json: Vec<BatchFileResult> =
+            serde_json::from_str(&batch_report(&results, "json").unwrap()).unwrap();
+        assert_eq!(json.len(), 2);
+        assert_eq!(json[1].error_message, results[1].error_message);
+        assert!(batch_report(&results, "xml").is_err());
+    }
+
+    #[test]
+    fn batch_launch_count_follows_mode() {
+        let dir = test_dir("batch_launch");
+        let cfg = AppConfig::default();
+        let destination = Destination::Temp(dir.join("temp"));
+        fs::create_dir_all(dir.join("temp")).unwrap();
+        let items: Vec<_> = ["a.txt", "b.txt", "c.txt"]
+            .iter()
+            .map(|name| {
+                let file = dir.join(name);
+                fs::write(&file, "plain text\n").unwrap();
+                let file = file.to_string_lossy().into_owned();
+                process_file(&file, &cfg, &destination, &DetectionCache::default())
+            })
+            .collect();
+
+        for (mode, expected) in [
+            (BatchLaunchMode::All, 3),
+            (BatchLaunchMode::FirstOnly, 1),
+            (BatchLaunchMode::None, 0),
+        ] {
+            let mut opened = Vec::new();
+            let launched = launch_items(&items, mode, |_, launch_path| {
+                opened.push(launch_path.to_string());
+                // A failed launch still counts and doesn't stop the rest
+                Err("no editor".to_string())
+            });
+            assert_eq!(launched, expected, "{:?}", mode);
+            assert_eq!(opened.len(), expected, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn same_path_twice_is_deduped() {
+        let dir = test_dir("dedupe");
+        let file_paths = write_shift_jis_files(&dir, 2);
+        let input = vec![
+            file_paths[0].clone(),
+            file_paths[1].clone(),
+            file_paths[0].clone(),
+        ];
+
+        let (unique, duplicates_skipped) = dedupe_paths(&input);
+
+        assert_eq!(unique, file_paths);
+        assert_eq!(duplicates_skipped, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_to_same_file_is_deduped() {
+        let dir = test_dir("dedupe_symlink");
+        let file_paths = write_shift_jis_files(&dir, 1);
+        let link = dir.join("link.txt");
+        std::os::unix::fs::symlink(&file_paths[0], &link).unwrap();
+        let input = vec![file_paths[0].clone(), link.to_string_lossy().into_owned()];
+
+        let (unique, duplicates_skipped) = dedupe_paths(&input);
+
+        assert_eq!(unique, file_paths);
+        assert_eq!(duplicates_skipped, 1);
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::config::AppConfig;
+use crate::encoder::{self, DetectionResult};
+
+/// What a cached detection was computed from. Any change means the file was rewritten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: SystemTime,
+    size: u64,
+}
+
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some(FileStamp {
+        modified: metadata.modified().ok()?,
+        size: metadata.len(),
+    })
+}
+
+/// Detection results keyed by `(path, mtime, size)`, kept for the app session in
+/// Tauri state so rescanning a folder skips re-scoring files that haven't changed.
+/// Only files that needed scoring are cached; UTF-8 files are cheap to re-check.
+#[derive(Debug, Default)]
+pub struct DetectionCache(Mutex<HashMap<PathBuf, (FileStamp, DetectionResult)>>);
+
+/// A cache lookup made before reading a file: the cached detection if the file is
+/// unchanged, else the stamp to store a fresh detection under. Stat'ing first means a
+/// cache hit needn't read the file, and a file rewritten while it was being read is
+/// detected again next time.
+#[derive(Debug)]
+pub struct CacheLookup {
+    stamp: Option<FileStamp>,
+    cached: Option<DetectionResult>,
+}
+
+impl CacheLookup {
+    /// The cached detection, if the file was unchanged.
+    pub fn cached(&self) -> Option<&DetectionResult> {
+        self.cached.as_ref()
+    }
+}
+
+impl DetectionCache {
+    /// Stat `path` and look up its detection. Call before reading the file.
+    /// Stale entries are dropped.
+    pub fn lookup(&self, path: &Path) -> CacheLookup {
+        let stamp = file_stamp(path);
+        let cached = stamp.and_then(|stamp| {
+            // A poisoned cache only costs a re-detection
+            let mut entries = self.0.lock().ok()?;
+            match entries.get(path) {
+                Some((cached, detection)) if *cached == stamp => Some(detection.clone()),
+                Some(_) => {
+                    entries.remove(path);
+                    None
+                }
+                None => None,
+            }
+        });
+        CacheLookup { stamp, cached }
+    }
+
+    /// `smart_detect_encoding` for `data`, read from `path` after `lookup`: the cached
+    /// result while the file was unchanged, otherwise a fresh one stored under the
+    /// lookup's stamp.
+    pub fn detect(
+        &self,
+        path: &Path,
+        lookup: CacheLookup,
+        data: &[u8],
+        cfg: &AppConfig,
+    ) -> DetectionResult {
+        if let Some(detection) = lookup.cached {
+            return detection;
+        }
+        let detection =
+            encoder::smart_detect_encoding(data, &cfg.candidate_encodings, &cfg.scoring_weights);
+        if let (Some(stamp), Ok(mut entries)) = (lookup.stamp, self.0.lock()) {
+            entries.insert(path.to_path_buf(), (stamp, detection.clone()));
+        }
+        detection
+    }
+
+    /// Forget every entry (e.g. after the candidate list or weights change).
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.0.lock() {
+            entries.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_file(name: &str, data: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("encodingman_cache_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("data.txt");
+        fs::write(&file, data).unwrap();
+        file
+    }
+
+    fn shift_jis() -> Vec<u8> {
+        encoding_rs::SHIFT_JIS
+            .encode("日本語のテキストです。こんにちは世界")
+            .0
+            .into_owned()
+    }
+
+    fn utf16le() -> Vec<u8> {
+        let mut data = vec![0xFF, 0xFE];
+        for unit in "日本語のテキストです".encode_utf16() {
+            data.extend(unit.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn changed_file_is_detected_again() {
+        let file = test_file("changed", &shift_jis());
+        let cfg = AppConfig::default();
+        let cache = DetectionCache::default();
+
+        let lookup = cache.lookup(&file);
+        assert!(lookup.cached().is_none());
+        let detection = cache.detect(&file, lookup, &shift_jis(), &cfg);
+        assert_eq!(detection.encoding_name, "Shift_JIS");
+        let cached = cache.lookup(&file).cached().cloned().unwrap();
+        assert_eq!(cached.encoding_name, "Shift_JIS");
+
+        fs::write(&file, utf16le()).unwrap();
+        let lookup = cache.lookup(&file);
+        assert!(lookup.cached().is_none());
+        let detection = cache.detect(&file, lookup, &utf16le(), &cfg);
+        assert_eq!(detection.encoding_name, "UTF-16LE");
+    }
+
+    #[test]
+    fn file_rewritten_after_lookup_is_not_cached_as_current() {
+        let file = test_file("rewritten", &shift_jis());
+        let cfg = AppConfig::default();
+        let cache = DetectionCache::default();
+
+        let lookup = cache.lookup(&file);
+        // Rewritten (with a different size) between the stat and the detection
+        fs::write(&file, utf16le()).unwrap();
+        cache.detect(&file, lookup, &shift_jis(), &cfg);
+
+        assert!(cache.lookup(&file).cached().is_none());
+    }
+}
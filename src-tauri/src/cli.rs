@@ -0,0 +1,195 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::cache::DetectionCache;
+use crate::config::{self, AppConfig};
+use crate::encoder::{self, DetectionOutcome, DetectionResult};
+
+/// A headless command-line mode. These never create a window or launch an app.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    /// `--stdout <file>`: convert the file and write UTF-8 to stdout.
+    Stdout(String),
+    /// `--detect <file>`: print the detected encoding and confidence.
+    Detect(String),
+    /// `--convert-stdin`: convert stdin and write UTF-8 to stdout.
+    ConvertStdin,
+}
+
+/// Parse CLI arguments (without the program name).
+/// Returns None when no CLI flag is present, so the GUI flow runs as before.
+pub fn parse_args(args: &[String]) -> Option<Result<CliCommand, String>> {
+    let flag = args.first()?;
+    if flag == "--convert-stdin" {
+        return Some(Ok(CliCommand::ConvertStdin));
+    }
+
+    let make: fn(String) -> CliCommand = match flag.as_str() {
+        "--stdout" => CliCommand::Stdout,
+        "--detect" => CliCommand::Detect,
+        _ => return None,
+    };
+
+    Some(match args.get(1) {
+        Some(file_path) => Ok(make(file_path.clone())),
+        None => Err(format!("Usage: encodingman {} <file>", flag)),
+    })
+}
+
+/// Run a CLI command and return the process exit code.
+pub fn run(command: CliCommand) -> i32 {
+    let cfg = config::load_config();
+
+    let result = match &command {
+        CliCommand::Stdout(file_path) => {
+            convert_file_to_utf8(Path::new(file_path), &cfg).and_then(|data| {
+                let mut stdout = std::io::stdout().lock();
+                stdout
+                    .write_all(&data)
+                    .and_then(|_| stdout.flush())
+                    .map_err(|e| format!("Failed to write to stdout: {}", e))
+            })
+        }
+        CliCommand::Detect(file_path) => detect_file(Path::new(file_path), &cfg).map(|detection| {
+            println!("{}\t{:.2}", detection.encoding_name, detection.confidence);
+        }),
+        CliCommand::ConvertStdin => encoder::convert_stream(
+            std::io::stdin().lock(),
+            std::io::stdout().lock(),
+            cfg.target_with_bom(),
+        ),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+/// Convert a file to UTF-8 per the config and return the bytes, without writing
+/// or launching anything. Already-UTF-8 files are returned unchanged.
+pub fn convert_file_to_utf8(path: &Path, cfg: &AppConfig) -> Result<Vec<u8>, String> {
+    if !path.exists() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+    if cfg.encoding_override(path).is_none() && encoder::is_binary_file(path) {
+        return Err(format!("Binary file, nothing to convert: {}", path.display()));
+    }
+
+    // One file per run, so there is nothing for a cache to remember
+    let cache = DetectionCache::default();
+    let lookup = cache.lookup(path);
+    let data = encoder::read_file_bytes(path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    match crate::detect_and_convert_data(path, &data, cfg, &cache, lookup)? {
+        DetectionOutcome::Converted { output, .. } => Ok(output.data),
+        _ => Ok(data),
+    }
+}
+
+/// Detect a file's encoding the same way the convert flow does, including any
+/// encoding override for it.
+pub fn detect_file(path: &Path, cfg: &AppConfig) -> Result<DetectionResult, String> {
+    if !path.exists() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+    if let Some(encoding) = cfg.encoding_override(path) {
+        return Ok(DetectionResult {
+            encoding_name: encoding.to_string(),
+            confidence: 1.0,
+        });
+    }
+    if encoder::is_binary_file(path) {
+        return Ok(DetectionResult {
+            encoding_name: "binary".to_string(),
+            confidence: 1.0,
+        });
+    }
+
+    let data = encoder::read_file_bytes(path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    if encoder::is_already_utf8(&data) {
+        return Ok(DetectionResult {
+            encoding_name: encoder::utf8_display_name(&data).to_string(),
+            confidence: 1.0,
+        });
+    }
+
+    Ok(encoder::smart_detect_encoding(
+        &data,
+        &cfg.candidate_encodings,
+        &cfg.scoring_weights,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("encodingman_cli_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn cli_flags_are_parsed() {
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            parse_args(&args(&["--stdout", "a.csv"])),
+            Some(Ok(CliCommand::Stdout("a.csv".to_string())))
+        );
+        assert_eq!(
+            parse_args(&args(&["--detect", "a.csv"])),
+            Some(Ok(CliCommand::Detect("a.csv".to_string())))
+        );
+        assert!(matches!(parse_args(&args(&["--detect"])), Some(Err(_))));
+        assert_eq!(parse_args(&args(&["a.csv"])), None);
+        assert_eq!(parse_args(&[]), None);
+    }
+
+    #[test]
+    fn cli_conversion_returns_utf8_bytes() {
+        let dir = test_dir("convert");
+        let path = dir.join("sjis.csv");
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("名前,住所\nやまだ,とうきょう\n");
+        std::fs::write(&path, &sjis[..]).unwrap();
+        let cfg = AppConfig {
+            target_encoding: "utf-8".to_string(),
+            ..AppConfig::default()
+        };
+
+        let data = convert_file_to_utf8(&path, &cfg).unwrap();
+        assert_eq!(data, "名前,住所\nやまだ,とうきょう\n".as_bytes());
+        assert_eq!(detect_file(&path, &cfg).unwrap().encoding_name, "Shift_JIS");
+
+        let utf8 = dir.join("utf8.csv");
+        std::fs::write(&utf8, "a,b\n").unwrap();
+        assert_eq!(convert_file_to_utf8(&utf8, &cfg).unwrap(), b"a,b\n");
+        assert!(convert_file_to_utf8(&dir.join("missing.csv"), &cfg).is_err());
+    }
+
+    #[test]
+    fn both_modes_honor_encoding_override() {
+        let file = test_dir("override").join("data.txt");
+        let text = "これはShift_JISで保存されたテキストです。\n";
+        std::fs::write(&file, encoding_rs::SHIFT_JIS.encode(text).0).unwrap();
+        let mut cfg = AppConfig::default();
+        assert_eq!(detect_file(&file, &cfg).unwrap().encoding_name, "Shift_JIS");
+
+        cfg.set_encoding_override(&file, Some("EUC-JP".to_string()));
+
+        let detection = detect_file(&file, &cfg).unwrap();
+        assert_eq!(detection.encoding_name, "EUC-JP");
+        assert_eq!(detection.confidence, 1.0);
+        let data = std::fs::read(&file).unwrap();
+        let expected = encoder::convert_with_options(&data, "EUC-JP", &cfg.convert_options())
+            .unwrap()
+            .data;
+        assert_eq!(convert_file_to_utf8(&file, &cfg).unwrap(), expected);
+    }
+}
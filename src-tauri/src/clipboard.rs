@@ -0,0 +1,138 @@
+use crate::config::AppConfig;
+use crate::encoder::{self, ConvertOptions, DetectionOutcome};
+
+/// Text recovered from raw bytes, plus where it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextConversion {
+    pub text: String,
+    /// Detected source encoding ("UTF-8" when nothing needed converting).
+    pub encoding_name: String,
+    pub confidence: f32,
+    /// Whether the text differs from a plain UTF-8 reading of the input.
+    pub changed: bool,
+}
+
+/// Detect and convert raw text bytes exactly like a file, but without a BOM
+/// (pasted text shouldn't start with U+FEFF). Binary-looking input is rejected.
+pub fn convert_text_bytes(data: &[u8], cfg: &AppConfig) -> Result<TextConversion, String> {
+    let options = ConvertOptions {
+        with_bom: false,
+        // The BOM is dropped from already-UTF-8 text anyway; that isn't a conversion
+        strip_existing_bom: false,
+        ..cfg.convert_options()
+    };
+    let outcome = encoder::detect_and_convert_bytes_with(
+        data,
+        encoder::is_binary_data(data),
+        &cfg.candidate_encodings,
+        &cfg.scoring_weights,
+        &options,
+    )?;
+
+    match outcome {
+        DetectionOutcome::Binary => Err("Clipboard content is not text".to_string()),
+        DetectionOutcome::AlreadyUtf8 { has_bom } => {
+            let text = if has_bom { &data[3..] } else { data };
+            Ok(TextConversion {
+                text: String::from_utf8_lossy(text).into_owned(),
+                encoding_name: "UTF-8".to_string(),
+                confidence: 1.0,
+                changed: false,
+            })
+        }
+        DetectionOutcome::Converted { detection, output } => Ok(TextConversion {
+            text: String::from_utf8(output.data)
+                .map_err(|e| format!("Converted text is not UTF-8: {}", e))?,
+            encoding_name: detection.encoding_name,
+            confidence: detection.confidence,
+            changed: true,
+        }),
+    }
+}
+
+/// Fix clipboard text. Clipboard APIs hand over a `String`, so legacy bytes only
+/// survive as mojibake: Shift_JIS and friends shown as Latin-1 ("“ú–{Œê").
+/// When the text maps back to bytes losslessly through windows-1252 and those bytes
+/// aren't UTF-8, they are treated as the raw input. The text is left unchanged unless
+/// that re-detection is confident and actually yields different text.
+pub fn fix_clipboard_text(text: &str, cfg: &AppConfig) -> Result<TextConversion, String> {
+    let raw = (!text.is_ascii())
+        .then(|| encoder::encode_without_bom(text, "windows-1252"))
+        .flatten()
+        .filter(|bytes| std::str::from_utf8(bytes).is_err());
+
+    let Some(bytes) = raw else {
+        return convert_text_bytes(text.as_bytes(), cfg);
+    };
+    let converted = convert_text_bytes(&bytes, cfg)?;
+    if converted.confidence < cfg.confidence_threshold || converted.text == text {
+        return convert_text_bytes(text.as_bytes(), cfg);
+    }
+    Ok(converted)
+}
+
+/// Read the clipboard, fix its text, and write the result back when it changed.
+/// Returns a short status for the UI.
+pub fn convert_clipboard(cfg: &AppConfig) -> Result<String, String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Failed to open clipboard: {}", e))?;
+    let text = clipboard
+        .get_text()
+        .map_err(|e| format!("Failed to read clipboard: {}", e))?;
+
+    let converted = fix_clipboard_text(&text, cfg)?;
+    if !converted.changed {
+        return Ok("Clipboard text is already UTF-8".to_string());
+    }
+
+    clipboard
+        .set_text(converted.text)
+        .map_err(|e| format!("Failed to write clipboard: {}", e))?;
+    Ok(format!("Converted from {}", converted.encoding_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shift_jis(text: &str) -> Vec<u8> {
+        encoding_rs::SHIFT_JIS.encode(text).0.into_owned()
+    }
+
+    #[test]
+    fn shift_jis_bytes_are_converted() {
+        let text = "これはクリップボードのテキストです。\n";
+        let converted = convert_text_bytes(&shift_jis(text), &AppConfig::default()).unwrap();
+        assert_eq!(converted.text, text);
+        assert_eq!(converted.encoding_name, "Shift_JIS");
+        assert!(converted.changed);
+    }
+
+    #[test]
+    fn windows_1252_mojibake_is_fixed() {
+        let text = "これはクリップボードのテキストです。";
+        let sjis = shift_jis(text);
+        let (mojibake, _) = encoding_rs::WINDOWS_1252.decode_without_bom_handling(&sjis);
+
+        let fixed = fix_clipboard_text(&mojibake, &AppConfig::default()).unwrap();
+        assert_eq!(fixed.text, text);
+        assert!(fixed.changed);
+    }
+
+    #[test]
+    fn ascii_and_utf8_text_is_unchanged() {
+        let cfg = AppConfig::default();
+        let ascii = fix_clipboard_text("plain,text\n", &cfg).unwrap();
+        assert!(!ascii.changed);
+        assert_eq!(ascii.text, "plain,text\n");
+
+        let utf8 = fix_clipboard_text("日本語のテキスト", &cfg).unwrap();
+        assert!(!utf8.changed);
+        assert_eq!(utf8.encoding_name, "UTF-8");
+    }
+
+    #[test]
+    fn binary_bytes_are_rejected() {
+        assert!(convert_text_bytes(b"%PDF-1.7\n\x00\x01", &AppConfig::default()).is_err());
+    }
+}
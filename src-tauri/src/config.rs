@@ -1,14 +1,29 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::batch::BatchLaunchMode;
+use crate::encoder::{ConvertOptions, LineEnding};
+use crate::scan::ScanOptions;
+use crate::{launcher, scorer};
+
+/// Current config schema version. Bump it and add a step to `migrate`
+/// whenever a change needs more than serde defaults (renames, new meanings).
+pub const CONFIG_VERSION: u32 = 2;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
+    /// Schema version of this config (see `CONFIG_VERSION`).
+    pub version: u32,
+
     /// Path to the default application to open CSV files.
     /// "system_default" means use the OS default handler.
     pub default_app: String,
 
-    /// Target encoding for conversion. Default is "utf-8-bom".
+    /// Target encoding for conversion: "utf-8-bom" (default) or "utf-8" (no BOM).
     pub target_encoding: String,
 
     /// Confidence threshold (0.0-1.0). Below this, show confirmation UI.
@@ -17,22 +32,226 @@ pub struct AppConfig {
     /// Number of lines to show in preview.
     pub preview_lines: usize,
 
+    /// Preview lines longer than this many characters are truncated. 0 = unlimited.
+    pub preview_max_width: usize,
+
     /// Whether to keep temp files after the app closes.
     pub keep_temp_file: bool,
+
+    /// Reuse an existing temp copy instead of rewriting it when it is at least as
+    /// new as the source file.
+    pub reuse_temp: bool,
+
+    /// Where converted output goes: "temp" (default) writes a `*_utf8` copy to the
+    /// temp directory, "in_place" overwrites the original file.
+    pub write_mode: String,
+
+    /// In "in_place" mode, copy the original to `<name>.bak` before overwriting it.
+    pub backup_before_overwrite: bool,
+
+    /// Which files a batch opens when it finishes: "all" (default), "none",
+    /// or "first_only".
+    pub batch_launch_mode: String,
+
+    /// Line-ending normalization: "preserve" (default), "lf", or "crlf".
+    pub line_ending: String,
+
+    /// Strip trailing spaces and tabs from every line of converted output.
+    pub trim_trailing_whitespace: bool,
+
+    /// Convert UTF-8 files that start with a BOM to BOM-less copies when the target is
+    /// "utf-8"; otherwise they are opened as they are.
+    pub strip_existing_bom: bool,
+
+    /// Final line break of converted output: true = exactly one, false = none,
+    /// None (default) = unchanged.
+    pub ensure_final_newline: Option<bool>,
+
+    /// Encoding labels tried by the smart detector, in tie-break priority order.
+    /// Unknown labels are ignored.
+    pub candidate_encodings: Vec<String>,
+
+    /// Weights of the detection score formula; omitted fields use the defaults.
+    pub scoring_weights: scorer::ScoringWeights,
+
+    /// Allow lossy conversion (U+FFFD substitution). When false, such conversions fail.
+    pub lossy_allowed: bool,
+
+    /// Check that each conversion re-encodes back to the original bytes (reported, never fatal).
+    pub verify_round_trip: bool,
+
+    /// Log level for %APPDATA%/encodingman/logs: "off", "error", "warn", "info" (default),
+    /// "debug", or "trace".
+    pub log_level: String,
+
+    /// Maximum folder depth followed when scanning folders.
+    pub max_scan_depth: usize,
+
+    /// Skip files and folders ignored by .gitignore when scanning folders.
+    pub respect_gitignore: bool,
+
+    /// Files larger than this are skipped by folder scans and batches. None = unlimited.
+    pub max_file_size_bytes: Option<u64>,
+
+    /// Directory for converted temp files. None = %TEMP%/encodingman.
+    pub temp_dir: Option<String>,
+
+    /// App to open files with, keyed by extension ("csv", "json", ...).
+    /// Extensions not listed here use `default_app`.
+    pub app_by_extension: HashMap<String, String>,
+
+    /// Arguments passed to the app; `{file}` marks where the path goes.
+    /// Without `{file}` the path is appended. Empty = just the path.
+    pub launch_args: Vec<String>,
+
+    /// Extra launch attempts after a transient failure (see `launcher::with_retries`).
+    pub launch_retries: u32,
+
+    /// Wait between launch attempts, in milliseconds.
+    pub launch_retry_delay_ms: u64,
+
+    /// Encoding forced for specific files, keyed by canonical absolute path
+    /// (see `override_key`). Files listed here skip detection.
+    pub encoding_overrides: HashMap<String, String>,
+
+    /// Files at least this many MiB are converted in chunks, streaming to the temp
+    /// file with progress events. 0 turns chunked conversion off.
+    pub chunked_threshold_mb: u64,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
             default_app: "system_default".to_string(),
             target_encoding: "utf-8-bom".to_string(),
             confidence_threshold: 0.75,
             preview_lines: 10,
+            preview_max_width: 500,
             keep_temp_file: false,
+            reuse_temp: true,
+            write_mode: "temp".to_string(),
+            backup_before_overwrite: true,
+            batch_launch_mode: "all".to_string(),
+            line_ending: "preserve".to_string(),
+            trim_trailing_whitespace: false,
+            ensure_final_newline: None,
+            strip_existing_bom: false,
+            candidate_encodings: scorer::DEFAULT_CANDIDATE_ENCODINGS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            scoring_weights: scorer::ScoringWeights::default(),
+            lossy_allowed: true,
+            verify_round_trip: false,
+            log_level: "info".to_string(),
+            max_scan_depth: ScanOptions::default().max_depth,
+            respect_gitignore: false,
+            max_file_size_bytes: None,
+            temp_dir: None,
+            app_by_extension: HashMap::new(),
+            launch_args: Vec::new(),
+            launch_retries: 2,
+            launch_retry_delay_ms: 500,
+            encoding_overrides: HashMap::new(),
+            chunked_threshold_mb: 64,
         }
     }
 }
 
+impl AppConfig {
+    /// Whether converted output should start with a UTF-8 BOM.
+    /// Only "utf-8" opts out; anything else keeps the BOM (Excel-friendly default).
+    pub fn target_with_bom(&self) -> bool {
+        !self.target_encoding.eq_ignore_ascii_case("utf-8")
+    }
+
+    /// Whether converted output overwrites the original file instead of a temp copy.
+    pub fn write_in_place(&self) -> bool {
+        self.write_mode.eq_ignore_ascii_case("in_place")
+    }
+
+    /// Whether a file of `size` bytes should be converted in chunks.
+    pub fn converts_chunked(&self, size: u64) -> bool {
+        self.chunked_threshold_mb > 0 && size >= self.chunked_threshold_mb * 1024 * 1024
+    }
+
+    /// Which files a finished batch opens.
+    pub fn batch_launch_mode(&self) -> BatchLaunchMode {
+        BatchLaunchMode::from_label(&self.batch_launch_mode)
+    }
+
+    /// Build the conversion options described by this config.
+    pub fn convert_options(&self) -> ConvertOptions {
+        ConvertOptions {
+            with_bom: self.target_with_bom(),
+            line_ending: LineEnding::from_label(&self.line_ending),
+            lossy_allowed: self.lossy_allowed,
+            verify_round_trip: self.verify_round_trip,
+            trim_trailing_whitespace: self.trim_trailing_whitespace,
+            ensure_final_newline: self.ensure_final_newline,
+            strip_existing_bom: self.strip_existing_bom,
+        }
+    }
+
+    /// The encoding forced for `path` by `encoding_overrides`, if any.
+    pub fn encoding_override(&self, path: &Path) -> Option<&str> {
+        if self.encoding_overrides.is_empty() {
+            return None;
+        }
+        self.encoding_overrides
+            .get(&override_key(path))
+            .map(|encoding| encoding.as_str())
+    }
+
+    /// Force `encoding` for `path`, or remove the override when `encoding` is None.
+    pub fn set_encoding_override(&mut self, path: &Path, encoding: Option<String>) {
+        let key = override_key(path);
+        match encoding {
+            Some(encoding) => {
+                self.encoding_overrides.insert(key, encoding);
+            }
+            None => {
+                self.encoding_overrides.remove(&key);
+            }
+        }
+    }
+
+    /// Check that values are in range before they are persisted.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.confidence_threshold) {
+            return Err(format!(
+                "confidence_threshold must be between 0.0 and 1.0, got {}",
+                self.confidence_threshold
+            ));
+        }
+        if self.preview_lines == 0 {
+            return Err("preview_lines must be at least 1".to_string());
+        }
+        launcher::validate_launch_args(&self.launch_args)?;
+        Ok(())
+    }
+
+    /// Build the folder-scan options described by this config.
+    pub fn scan_options(&self) -> ScanOptions {
+        ScanOptions {
+            max_depth: self.max_scan_depth,
+            max_file_size: self.max_file_size_bytes,
+            respect_gitignore: self.respect_gitignore,
+            ..ScanOptions::default()
+        }
+    }
+}
+
+/// Key for `encoding_overrides`: the canonical absolute path, so the same file
+/// matches however it was opened. Paths that can't be resolved are used as given.
+pub fn override_key(path: &Path) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
 /// Get the config file path: %APPDATA%/encodingman/config.json
 fn config_path() -> PathBuf {
     let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
@@ -41,21 +260,159 @@ fn config_path() -> PathBuf {
     path
 }
 
-/// Load config from disk. Returns default if file doesn't exist.
+/// Why the config file on disk couldn't be used.
+#[derive(Debug, Clone)]
+pub enum ConfigError {
+    /// The file exists but couldn't be read.
+    Io(String),
+    /// The file isn't valid config JSON. It was renamed to `backup_path`
+    /// (None if the rename failed too) so the next save doesn't overwrite it.
+    Parse {
+        message: String,
+        backup_path: Option<PathBuf>,
+    },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(message) => write!(f, "Failed to read config file: {}", message),
+            ConfigError::Parse {
+                message,
+                backup_path: Some(backup),
+            } => write!(
+                f,
+                "Config file was invalid and has been moved to {}: {}",
+                backup.display(),
+                message
+            ),
+            ConfigError::Parse {
+                message,
+                backup_path: None,
+            } => write!(f, "Config file was invalid: {}", message),
+        }
+    }
+}
+
+/// The last error `load_config` swallowed, kept for the UI to report.
+static LAST_LOAD_ERROR: Mutex<Option<ConfigError>> = Mutex::new(None);
+
+/// Load config from disk. Returns default if the file doesn't exist.
+/// A corrupt file is backed up (see `load_config_result`) and the default is used;
+/// the error can be picked up later with `take_load_error`.
 pub fn load_config() -> AppConfig {
-    let path = config_path();
+    or_default_remembering_error(load_config_result())
+}
+
+/// The loaded config, or the default with the error kept for `take_load_error`.
+fn or_default_remembering_error(result: Result<AppConfig, ConfigError>) -> AppConfig {
+    result.unwrap_or_else(|e| {
+        if let Ok(mut last) = LAST_LOAD_ERROR.lock() {
+            *last = Some(e);
+        }
+        AppConfig::default()
+    })
+}
+
+/// Load config from disk, reporting why the file couldn't be used.
+/// A missing file is not an error and yields the default.
+pub fn load_config_result() -> Result<AppConfig, ConfigError> {
+    load_config_from(&config_path())
+}
+
+/// Load config from `path`. On a parse error the file is renamed to `<name>.bak`.
+pub fn load_config_from(path: &Path) -> Result<AppConfig, ConfigError> {
     if !path.exists() {
-        return AppConfig::default();
+        return Ok(AppConfig::default());
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| ConfigError::Io(e.to_string()))?;
+    serde_json::from_str(&content).and_then(migrate).map_err(|e| {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".bak");
+        let backup = PathBuf::from(backup);
+        ConfigError::Parse {
+            message: e.to_string(),
+            backup_path: fs::rename(path, &backup).ok().map(|_| backup),
+        }
+    })
+}
+
+/// Upgrade a raw config from whatever version wrote it to `CONFIG_VERSION`.
+/// A missing `version` means v0 (before versioning). Configs from a newer
+/// version are parsed as-is and keep their version, so `save_config` won't clobber them.
+pub fn migrate(raw: serde_json::Value) -> Result<AppConfig, serde_json::Error> {
+    let mut raw = raw;
+    let mut version = raw
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .map_or(0, |v| v.min(u32::MAX as u64) as u32);
+
+    if version > CONFIG_VERSION {
+        return serde_json::from_value(raw);
+    }
+
+    while version < CONFIG_VERSION {
+        match version {
+            // v0 → v1: fields added since v0 are filled in by serde defaults
+            0 => {}
+            // v1 → v2: an untouched default candidate list gains the Latin codepages
+            1 => add_latin_candidates(&mut raw),
+            _ => unreachable!("no migration step for config version {}", version),
+        }
+        version += 1;
     }
 
-    match fs::read_to_string(&path) {
-        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
-        Err(_) => AppConfig::default(),
+    if let Some(obj) = raw.as_object_mut() {
+        obj.insert("version".to_string(), CONFIG_VERSION.into());
+    }
+    serde_json::from_value(raw)
+}
+
+/// Default `candidate_encodings` before the Latin single-byte codepages were added.
+const V1_DEFAULT_CANDIDATES: &[&str] = &[
+    "Shift_JIS",
+    "EUC-JP",
+    "ISO-2022-JP",
+    "UTF-8",
+    "UTF-16LE",
+    "UTF-16BE",
+    "GBK",
+    "Big5",
+    "windows-1252",
+];
+
+fn add_latin_candidates(raw: &mut serde_json::Value) {
+    let Some(list) = raw.get_mut("candidate_encodings").and_then(|v| v.as_array_mut()) else {
+        return;
+    };
+    let is_v1_default = list.len() == V1_DEFAULT_CANDIDATES.len()
+        && list.iter().zip(V1_DEFAULT_CANDIDATES).all(|(v, d)| v.as_str() == Some(*d));
+    // A customized list is the user's choice; leave it alone
+    if is_v1_default {
+        *list = scorer::DEFAULT_CANDIDATE_ENCODINGS
+            .iter()
+            .map(|s| serde_json::Value::from(*s))
+            .collect();
     }
 }
 
+/// Take (and clear) the error from the last `load_config` that fell back to defaults.
+pub fn take_load_error() -> Option<ConfigError> {
+    LAST_LOAD_ERROR.lock().ok().and_then(|mut last| last.take())
+}
+
 /// Save config to disk.
+/// Invalid values are rejected with a descriptive error and nothing is written.
 pub fn save_config(config: &AppConfig) -> Result<(), String> {
+    config.validate()?;
+    if config.version > CONFIG_VERSION {
+        return Err(format!(
+            "Config is from a newer version (v{}, supported v{}); not overwriting it",
+            config.version, CONFIG_VERSION
+        ));
+    }
+
     let path = config_path();
 
     // Ensure parent directory exists
@@ -70,3 +427,117 @@ pub fn save_config(config: &AppConfig) -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("encodingman_config_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn malformed_config_is_backed_up_and_defaults_used() {
+        let dir = test_dir("malformed");
+        let path = dir.join("config.json");
+        fs::write(&path, "{ \"default_app\": ").unwrap();
+
+        let cfg = or_default_remembering_error(load_config_from(&path));
+
+        assert_eq!(cfg.default_app, AppConfig::default().default_app);
+        assert!(!path.exists());
+        let backup = dir.join("config.json.bak");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "{ \"default_app\": ");
+        match take_load_error() {
+            Some(ConfigError::Parse { backup_path, .. }) => {
+                assert_eq!(backup_path, Some(backup));
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(take_load_error().is_none());
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_values() {
+        assert!(AppConfig::default().validate().is_ok());
+
+        for threshold in [0.0, 1.0] {
+            let cfg = AppConfig {
+                confidence_threshold: threshold,
+                ..AppConfig::default()
+            };
+            assert!(cfg.validate().is_ok());
+        }
+        for threshold in [-0.01, 1.01, f32::NAN] {
+            let cfg = AppConfig {
+                confidence_threshold: threshold,
+                ..AppConfig::default()
+            };
+            assert!(cfg.validate().unwrap_err().contains("confidence_threshold"));
+        }
+
+        let cfg = AppConfig {
+            preview_lines: 0,
+            ..AppConfig::default()
+        };
+        assert!(cfg.validate().unwrap_err().contains("preview_lines"));
+
+        let cfg = AppConfig {
+            launch_args: vec!["{file}".to_string(), "--diff={file}".to_string()],
+            ..AppConfig::default()
+        };
+        assert!(cfg.validate().unwrap_err().contains("{file}"));
+    }
+
+    #[test]
+    fn versionless_config_migrates_to_current() {
+        let raw = serde_json::json!({
+            "default_app": "notepad.exe",
+        });
+
+        let cfg = migrate(raw).unwrap();
+
+        assert_eq!(cfg.version, CONFIG_VERSION);
+        assert_eq!(cfg.default_app, "notepad.exe");
+        assert_eq!(cfg.candidate_encodings, scorer::DEFAULT_CANDIDATE_ENCODINGS);
+    }
+
+    #[test]
+    fn customized_candidates_survive_migration() {
+        let raw = serde_json::json!({
+            "version": 1,
+            "candidate_encodings": ["Shift_JIS", "UTF-8"],
+        });
+
+        let cfg = migrate(raw).unwrap();
+
+        assert_eq!(cfg.version, CONFIG_VERSION);
+        assert_eq!(cfg.candidate_encodings, ["Shift_JIS", "UTF-8"]);
+    }
+
+    #[test]
+    fn config_from_newer_version_is_not_saved() {
+        let raw = serde_json::json!({ "version": CONFIG_VERSION + 1 });
+
+        let cfg = migrate(raw).unwrap();
+
+        assert_eq!(cfg.version, CONFIG_VERSION + 1);
+        assert!(save_config(&cfg).unwrap_err().contains("newer version"));
+    }
+
+    #[test]
+    fn v1_default_candidates_gain_latin_codepages() {
+        let raw = serde_json::json!({
+            "version": 1,
+            "candidate_encodings": V1_DEFAULT_CANDIDATES,
+        });
+
+        let cfg = migrate(raw).unwrap();
+
+        assert_eq!(cfg.version, CONFIG_VERSION);
+        assert_eq!(cfg.candidate_encodings, scorer::DEFAULT_CANDIDATE_ENCODINGS);
+    }
+}
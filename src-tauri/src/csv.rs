@@ -0,0 +1,120 @@
+use std::path::Path;
+
+/// Delimiters tried by `detect_delimiter`, in tie-break priority order.
+const CANDIDATE_DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+/// Number of records sampled from the start of the text.
+const SAMPLE_RECORDS: usize = 20;
+
+/// Whether delimiter detection applies to this file (.csv / .tsv).
+pub fn is_delimited_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("csv") || e.eq_ignore_ascii_case("tsv"))
+        .unwrap_or(false)
+}
+
+/// Pick the delimiter that splits the first records into the most consistent
+/// number of columns. Delimiters inside double-quoted fields don't count, and a
+/// quoted field may span lines. Falls back to ',' when no candidate appears.
+pub fn detect_delimiter(text: &str) -> char {
+    let mut best = (',', 0usize, 0usize); // (delimiter, consistent records, columns)
+    for delimiter in CANDIDATE_DELIMITERS {
+        let counts = delimiter_counts(text, delimiter);
+        let Some((columns, consistent)) = most_common_count(&counts) else {
+            continue;
+        };
+        // More consistent records wins; then more columns; then candidate order
+        if (consistent, columns) > (best.1, best.2) {
+            best = (delimiter, consistent, columns);
+        }
+    }
+    best.0
+}
+
+/// Display label for a delimiter in command results ("\t" is spelled out).
+pub fn delimiter_label(delimiter: char) -> String {
+    match delimiter {
+        '\t' => "\\t".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Quote a field for comma-separated output when it contains a comma, a quote,
+/// or a line break; embedded quotes are doubled.
+pub fn quote_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Count unquoted `delimiter`s per record in the first `SAMPLE_RECORDS` non-empty records.
+fn delimiter_counts(text: &str, delimiter: char) -> Vec<usize> {
+    let mut counts = Vec::new();
+    let mut count = 0;
+    let mut in_quotes = false;
+    let mut record_empty = true;
+
+    for ch in text.chars() {
+        match ch {
+            // An escaped quote ("") toggles twice, which leaves the state unchanged
+            '"' => in_quotes = !in_quotes,
+            '\n' | '\r' if !in_quotes => {
+                if !record_empty {
+                    counts.push(count);
+                    if counts.len() == SAMPLE_RECORDS {
+                        return counts;
+                    }
+                }
+                count = 0;
+                record_empty = true;
+                continue;
+            }
+            c if c == delimiter && !in_quotes => count += 1,
+            _ => {}
+        }
+        record_empty = false;
+    }
+    if !record_empty {
+        counts.push(count);
+    }
+    counts
+}
+
+/// The most frequent non-zero count and how many records have it.
+fn most_common_count(counts: &[usize]) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    for &count in counts.iter().filter(|&&c| c > 0) {
+        let records = counts.iter().filter(|&&c| c == count).count();
+        match best {
+            Some((_, best_records)) if best_records >= records => {}
+            _ => best = Some((count, records)),
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delimiter_is_detected() {
+        assert_eq!(detect_delimiter("a,b,c\n1,2,3\n"), ',');
+        assert_eq!(detect_delimiter("a;b;c\n1;2;3\n"), ';');
+        assert_eq!(detect_delimiter("a\tb\tc\n1\t2\t3\n"), '\t');
+        assert_eq!(detect_delimiter("no delimiters here"), ',');
+    }
+
+    #[test]
+    fn quoted_delimiters_are_ignored() {
+        // Commas only inside quotes; semicolons are the real separator
+        let text = "\"a,b\";c\n\"1,2,3\";4\n\"x\ny,z\";5\n";
+        assert_eq!(detect_delimiter(text), ';');
+        assert_eq!(delimiter_label('\t'), "\\t");
+        assert!(is_delimited_file(Path::new("data.TSV")));
+        assert!(!is_delimited_file(Path::new("notes.txt")));
+    }
+}
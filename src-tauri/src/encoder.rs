@@ -1,7 +1,8 @@
 use chardetng::EncodingDetector;
-use encoding_rs::Encoding;
-use std::fs;
-use std::io;
+use encoding_rs::{CoderResult, EncoderResult, Encoding};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
 use std::path::Path;
 
 use crate::scorer;
@@ -12,33 +13,265 @@ pub struct DetectionResult {
     pub confidence: f32,
 }
 
+/// Line-ending normalization mode applied to converted output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Keep line endings exactly as in the source.
+    Preserve,
+    /// Normalize every line break to "\n".
+    Lf,
+    /// Normalize every line break to "\r\n".
+    Crlf,
+}
+
+impl LineEnding {
+    /// Parse a config value ("preserve", "lf", "crlf"). Unknown values fall back to Preserve.
+    pub fn from_label(label: &str) -> Self {
+        match label.to_ascii_lowercase().as_str() {
+            "lf" => LineEnding::Lf,
+            "crlf" => LineEnding::Crlf,
+            _ => LineEnding::Preserve,
+        }
+    }
+}
+
+/// Line-ending style found in a decoded text, reported for diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEndingKind {
+    /// No line breaks at all.
+    None,
+    Lf,
+    Crlf,
+    /// Lone "\r" breaks (classic Mac).
+    Cr,
+    /// More than one style in the same text.
+    Mixed,
+}
+
+impl LineEndingKind {
+    /// Lowercase label used in command results ("none", "lf", "crlf", "cr", "mixed").
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEndingKind::None => "none",
+            LineEndingKind::Lf => "lf",
+            LineEndingKind::Crlf => "crlf",
+            LineEndingKind::Cr => "cr",
+            LineEndingKind::Mixed => "mixed",
+        }
+    }
+}
+
+/// Options controlling how decoded text is written back out as UTF-8.
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    pub with_bom: bool,
+    pub line_ending: LineEnding,
+    /// When false, malformed input fails the conversion instead of becoming U+FFFD.
+    pub lossy_allowed: bool,
+    /// Re-encode the decoded text and compare it with the source bytes.
+    pub verify_round_trip: bool,
+    /// Strip spaces and tabs from the end of every line.
+    pub trim_trailing_whitespace: bool,
+    /// Some(true): end with exactly one line break. Some(false): end with none.
+    /// None: leave the end of the text alone.
+    pub ensure_final_newline: Option<bool>,
+    /// Rewrite UTF-8 input that starts with a BOM when `with_bom` is false,
+    /// instead of treating it as already converted.
+    pub strip_existing_bom: bool,
+}
+
+/// Converted bytes plus what was lost on the way.
+#[derive(Debug, Clone)]
+pub struct ConvertOutput {
+    pub data: Vec<u8>,
+    /// Number of U+FFFD replacement characters produced while decoding.
+    pub replacement_count: usize,
+    /// Whether the decoder hit malformed input (i.e. characters were lost).
+    pub lossy: bool,
+    /// Whether the decoded text encodes back to exactly the source bytes.
+    /// None when `verify_round_trip` was off. Informational only: some encodings
+    /// (e.g. ISO-2022-JP escapes) can re-encode differently without losing text.
+    pub round_trip_ok: Option<bool>,
+}
+
+/// Text encoded into a target other than UTF-8, plus what it couldn't represent.
+#[derive(Debug, Clone)]
+pub struct EncodeOutput {
+    pub data: Vec<u8>,
+    /// Characters the target encoding has no mapping for, written as `?`.
+    pub unmappable_count: usize,
+}
+
+/// What `convert_chunked` lost on the way; the converted bytes went to its writer.
+#[derive(Debug, Clone)]
+pub struct ChunkedOutput {
+    pub replacement_count: usize,
+    pub lossy: bool,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            with_bom: true,
+            line_ending: LineEnding::Preserve,
+            lossy_allowed: true,
+            verify_round_trip: false,
+            trim_trailing_whitespace: false,
+            ensure_final_newline: None,
+            strip_existing_bom: false,
+        }
+    }
+}
+
+impl ConvertOptions {
+    /// The settings that change the converted bytes, as text. Copies written under
+    /// different settings get different temp names (see `launcher::temp_file_name`).
+    pub fn output_key(&self) -> String {
+        format!(
+            "bom={} strip={} eol={:?} trim={} final={:?}",
+            self.with_bom,
+            self.strip_existing_bom,
+            self.line_ending,
+            self.trim_trailing_whitespace,
+            self.ensure_final_newline
+        )
+    }
+
+    /// Whether already-UTF-8 `data` still needs converting to drop its BOM.
+    pub fn strips_bom(&self, data: &[u8]) -> bool {
+        self.strip_existing_bom && !self.with_bom && has_utf8_bom(data)
+    }
+}
+
+/// What `detect_and_convert_bytes` found, and the converted bytes when a conversion ran.
+#[derive(Debug, Clone)]
+pub enum DetectionOutcome {
+    /// Binary data: pass it through untouched.
+    Binary,
+    /// Already valid UTF-8: nothing to convert.
+    AlreadyUtf8 { has_bom: bool },
+    /// Decoded from `detection.encoding_name` and re-encoded as UTF-8.
+    Converted {
+        detection: DetectionResult,
+        output: ConvertOutput,
+    },
+}
+
+impl DetectionOutcome {
+    /// Encoding name to report: "binary", "UTF-8" / "UTF-8 (BOM)", or the detected encoding.
+    pub fn encoding_name(&self) -> &str {
+        match self {
+            DetectionOutcome::Binary => "binary",
+            DetectionOutcome::AlreadyUtf8 { has_bom: true } => "UTF-8 (BOM)",
+            DetectionOutcome::AlreadyUtf8 { has_bom: false } => "UTF-8",
+            DetectionOutcome::Converted { detection, .. } => &detection.encoding_name,
+        }
+    }
+
+    /// Detection confidence; binary and already-UTF-8 outcomes are certain.
+    pub fn confidence(&self) -> f32 {
+        match self {
+            DetectionOutcome::Converted { detection, .. } => detection.confidence,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Detect and convert in-memory bytes with the default candidates, weights, and
+/// options. Binary-ness is judged from the content alone. Nothing touches the
+/// filesystem or launches anything.
+pub fn detect_and_convert_bytes(
+    data: &[u8],
+    target_with_bom: bool,
+) -> Result<DetectionOutcome, String> {
+    let candidates: Vec<String> = scorer::DEFAULT_CANDIDATE_ENCODINGS
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let options = ConvertOptions {
+        with_bom: target_with_bom,
+        ..ConvertOptions::default()
+    };
+    detect_and_convert_bytes_with(
+        data,
+        is_binary_data(data),
+        &candidates,
+        &scorer::ScoringWeights::default(),
+        &options,
+    )
+}
+
+/// Detect and convert in-memory bytes with explicit settings. `is_binary` is the
+/// caller's verdict (e.g. from `is_binary_file`, which also looks at the extension).
+pub fn detect_and_convert_bytes_with(
+    data: &[u8],
+    is_binary: bool,
+    candidates: &[String],
+    weights: &scorer::ScoringWeights,
+    options: &ConvertOptions,
+) -> Result<DetectionOutcome, String> {
+    detect_and_convert_bytes_using(data, is_binary, options, |data| {
+        smart_detect_encoding(data, candidates, weights)
+    })
+}
+
+/// `detect_and_convert_bytes_with` with `detect` in place of `smart_detect_encoding`
+/// (e.g. a `DetectionCache`). It only runs for data that isn't binary or UTF-8.
+pub fn detect_and_convert_bytes_using(
+    data: &[u8],
+    is_binary: bool,
+    options: &ConvertOptions,
+    detect: impl FnOnce(&[u8]) -> DetectionResult,
+) -> Result<DetectionOutcome, String> {
+    if is_binary {
+        return Ok(DetectionOutcome::Binary);
+    }
+    if is_already_utf8(data) {
+        if options.strips_bom(data) {
+            let output = convert_with_options(data, "UTF-8", options)?;
+            let detection = DetectionResult {
+                encoding_name: "UTF-8".to_string(),
+                confidence: 1.0,
+            };
+            return Ok(DetectionOutcome::Converted { detection, output });
+        }
+        return Ok(DetectionOutcome::AlreadyUtf8 {
+            has_bom: has_utf8_bom(data),
+        });
+    }
+
+    let detection = detect(data);
+    let output = convert_with_options(data, &detection.encoding_name, options)?;
+    Ok(DetectionOutcome::Converted { detection, output })
+}
+
 /// Smart encoding detection: tries all candidate encodings and picks the best one.
 /// Uses BOM detection first, then falls back to multi-encoding scoring.
-pub fn smart_detect_encoding(data: &[u8]) -> DetectionResult {
+pub fn smart_detect_encoding(
+    data: &[u8],
+    candidates: &[String],
+    weights: &scorer::ScoringWeights,
+) -> DetectionResult {
     // Check for BOM markers first (100% confidence)
-    if data.len() >= 3 && data[0] == 0xEF && data[1] == 0xBB && data[2] == 0xBF {
+    if let Some((encoding_name, _)) = detect_bom(data) {
         return DetectionResult {
-            encoding_name: "UTF-8".to_string(),
+            encoding_name: encoding_name.to_string(),
             confidence: 1.0,
         };
     }
-    if data.len() >= 2 {
-        if data[0] == 0xFF && data[1] == 0xFE {
-            return DetectionResult {
-                encoding_name: "UTF-16LE".to_string(),
-                confidence: 1.0,
-            };
-        }
-        if data[0] == 0xFE && data[1] == 0xFF {
-            return DetectionResult {
-                encoding_name: "UTF-16BE".to_string(),
-                confidence: 1.0,
-            };
-        }
+
+    if let Some(utf16) = detect_bomless_utf16(data) {
+        return utf16;
+    }
+
+    // A long clean UTF-8 prefix means UTF-8 with a few broken bytes later on;
+    // scoring the whole file under every candidate would only confirm it
+    if let Some(utf8) = detect_utf8_prefix(data, candidates) {
+        return utf8;
     }
 
     // Use scorer to try all encodings and pick the best
-    let best = scorer::best_encoding(data);
+    let best = scorer::best_encoding(data, candidates, weights);
 
     DetectionResult {
         encoding_name: best.encoding_name,
@@ -46,6 +279,153 @@ pub fn smart_detect_encoding(data: &[u8]) -> DetectionResult {
     }
 }
 
+/// Identify a byte order mark: returns the encoding and the BOM length.
+/// UTF-32LE (FF FE 00 00) is checked before UTF-16LE (FF FE), whose BOM it starts with.
+pub fn detect_bom(data: &[u8]) -> Option<(&'static str, usize)> {
+    if data.starts_with(&[0xFF, 0xFE, 0x00, 0x00]) {
+        Some(("UTF-32LE", 4))
+    } else if data.starts_with(&[0x00, 0x00, 0xFE, 0xFF]) {
+        Some(("UTF-32BE", 4))
+    } else if data.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        Some(("UTF-8", 3))
+    } else if data.starts_with(&[0xFF, 0xFE]) {
+        Some(("UTF-16LE", 2))
+    } else if data.starts_with(&[0xFE, 0xFF]) {
+        Some(("UTF-16BE", 2))
+    } else {
+        None
+    }
+}
+
+/// Bytes of the prefix checked by `detect_utf8_prefix`.
+const UTF8_SAMPLE_LEN: usize = 64 * 1024;
+
+/// Multibyte sequences the UTF-8 prefix needs before it is trusted; an ASCII-only
+/// prefix says nothing about the rest of the file.
+const UTF8_MIN_MULTIBYTE: usize = 16;
+
+/// Confidence reported for the UTF-8 prefix fast path.
+const UTF8_PREFIX_CONFIDENCE: f32 = 0.95;
+
+/// Fast path for data that isn't entirely valid UTF-8: when the first
+/// `UTF8_SAMPLE_LEN` bytes are valid UTF-8 (no lone high bytes) with at least
+/// `UTF8_MIN_MULTIBYTE` multibyte sequences, the data is UTF-8 with damage further on.
+/// A sequence cut off by the sample boundary still counts as valid.
+/// Returns None when UTF-8 isn't among `candidates`.
+pub fn detect_utf8_prefix(data: &[u8], candidates: &[String]) -> Option<DetectionResult> {
+    let utf8_allowed = candidates
+        .iter()
+        .any(|label| canonical_encoding_name(label) == Some("UTF-8"));
+    if !utf8_allowed {
+        return None;
+    }
+
+    let sample = &data[..data.len().min(UTF8_SAMPLE_LEN)];
+    let valid = match std::str::from_utf8(sample) {
+        Ok(text) => text,
+        // error_len() is None when the sample merely ends mid-sequence
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&sample[..e.valid_up_to()]).ok()?
+        }
+        Err(_) => return None,
+    };
+
+    let multibyte = valid.chars().filter(|ch| !ch.is_ascii()).count();
+    (multibyte >= UTF8_MIN_MULTIBYTE).then(|| DetectionResult {
+        encoding_name: "UTF-8".to_string(),
+        confidence: UTF8_PREFIX_CONFIDENCE,
+    })
+}
+
+/// Bytes inspected when looking for BOM-less UTF-16.
+const UTF16_SAMPLE_LEN: usize = 8192;
+
+/// Minimum share of NULs in one byte position (odd for LE, even for BE).
+const UTF16_NUL_RATIO_MIN: f64 = 0.6;
+
+/// Maximum share of NULs tolerated in the other byte position.
+const UTF16_NUL_RATIO_OTHER_MAX: f64 = 0.1;
+
+/// Detect UTF-16 without a BOM from alternating NUL bytes.
+/// Mostly-ASCII UTF-16LE has NULs in odd positions, UTF-16BE in even positions.
+/// Confidence scales with how consistent the pattern is.
+pub fn detect_bomless_utf16(data: &[u8]) -> Option<DetectionResult> {
+    let sample = &data[..data.len().min(UTF16_SAMPLE_LEN) & !1];
+    let units = sample.len() / 2;
+    if units < 2 {
+        return None;
+    }
+
+    let even_nuls = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_nuls = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    let even_ratio = even_nuls as f64 / units as f64;
+    let odd_ratio = odd_nuls as f64 / units as f64;
+
+    let (encoding_name, ratio) = if odd_ratio >= UTF16_NUL_RATIO_MIN
+        && even_ratio <= UTF16_NUL_RATIO_OTHER_MAX
+    {
+        ("UTF-16LE", odd_ratio)
+    } else if even_ratio >= UTF16_NUL_RATIO_MIN && odd_ratio <= UTF16_NUL_RATIO_OTHER_MAX {
+        ("UTF-16BE", even_ratio)
+    } else {
+        return None;
+    };
+
+    // Reject samples that don't decode cleanly (the sample may cut a surrogate pair)
+    let encoding = Encoding::for_label(encoding_name.as_bytes())?;
+    let (text, _) = encoding.decode_without_bom_handling(sample);
+    let replacements = text.chars().filter(|&c| c == '\u{FFFD}').count();
+    if replacements > 1 {
+        return None;
+    }
+
+    Some(DetectionResult {
+        encoding_name: encoding_name.to_string(),
+        confidence: (0.5 + 0.45 * ratio) as f32,
+    })
+}
+
+/// Full detection report: every candidate's score, best first.
+/// When a BOM is present it is reported as a synthetic top entry with score 1.0,
+/// matching the short-circuit in `smart_detect_encoding`; BOM-less UTF-16 is
+/// re-ranked by its heuristic confidence, so the list stays sorted.
+pub fn detection_report(
+    data: &[u8],
+    candidates: &[String],
+    weights: &scorer::ScoringWeights,
+) -> Vec<scorer::EncodingScore> {
+    let mut scores = scorer::score_all_encodings(data, candidates, weights);
+
+    let forced = match detect_bom(data) {
+        Some((name, _)) => Some((name.to_string(), 1.0)),
+        None => detect_bomless_utf16(data).map(|d| (d.encoding_name, d.confidence as f64)),
+    };
+
+    if let Some((name, score)) = forced {
+        let mut entry = scores
+            .iter()
+            .position(|s| s.encoding_name == name)
+            .map(|i| scores.remove(i))
+            .unwrap_or(scorer::EncodingScore {
+                encoding_name: name.clone(),
+                score: 0.0,
+                replacement_count: 0,
+                cjk_char_count: 0,
+                total_chars: 0,
+                structural_errors: 0,
+            });
+        entry.score = score;
+        // Ahead of equal scores, so a BOM entry always comes first
+        let at = scores
+            .iter()
+            .position(|s| s.score <= entry.score)
+            .unwrap_or(scores.len());
+        scores.insert(at, entry);
+    }
+
+    scores
+}
+
 /// Legacy encoding detection using chardetng (kept for backward compatibility).
 pub fn detect_encoding(data: &[u8]) -> DetectionResult {
     // Check for BOM markers first
@@ -104,47 +484,529 @@ fn estimate_confidence(data: &[u8], encoding: &'static Encoding) -> f32 {
 }
 
 /// Check if data is already valid UTF-8 (with or without BOM).
+/// ASCII-heavy BOM-less UTF-16 is technically valid UTF-8 (NULs included), so it is excluded.
 pub fn is_already_utf8(data: &[u8]) -> bool {
-    let content = if data.len() >= 3 && data[0] == 0xEF && data[1] == 0xBB && data[2] == 0xBF {
-        &data[3..]
-    } else {
-        data
+    let content = if has_utf8_bom(data) { &data[3..] } else { data };
+    std::str::from_utf8(content).is_ok() && detect_bomless_utf16(content).is_none()
+}
+
+/// `is_already_utf8` for the head of a longer file: a multibyte sequence cut off by
+/// the end of the sample still counts as valid.
+pub fn is_utf8_prefix(sample: &[u8]) -> bool {
+    let content = if has_utf8_bom(sample) { &sample[3..] } else { sample };
+    let valid = match std::str::from_utf8(content) {
+        Ok(_) => true,
+        // error_len() is None when the sample merely ends mid-sequence
+        Err(e) => e.error_len().is_none(),
     };
-    std::str::from_utf8(content).is_ok()
+    valid && detect_bomless_utf16(content).is_none()
+}
+
+/// Whether the data starts with the UTF-8 BOM (EF BB BF).
+pub fn has_utf8_bom(data: &[u8]) -> bool {
+    data.starts_with(&[0xEF, 0xBB, 0xBF])
+}
+
+/// Label reported for already-UTF-8 data: "UTF-8 (BOM)" or "UTF-8", so users can
+/// spot a BOM that some tools trip over. Not an encoding label for conversion.
+pub fn utf8_display_name(data: &[u8]) -> &'static str {
+    if has_utf8_bom(data) {
+        "UTF-8 (BOM)"
+    } else {
+        "UTF-8"
+    }
+}
+
+/// Encodings UTF-8 text is commonly misread as before being saved again as UTF-8.
+const DOUBLE_ENCODING_SOURCES: &[&str] = &["Shift_JIS", "windows-1252"];
+
+/// Multi-byte characters the reversed text must contain before it is trusted.
+const DOUBLE_ENCODING_MIN_CHARS: usize = 2;
+
+/// Recover text that was UTF-8, misread as Shift_JIS or windows-1252 and saved again
+/// as UTF-8 (mojibake like "æ—¥æœ¬" or "譚ｱ莠ｬ驛ｽ"). Re-encoding the text in the
+/// misread encoding gives back the original bytes; ordinary text practically never
+/// re-encodes to valid UTF-8, so that is the signal. Returns the recovered text.
+pub fn detect_double_encoding(text: &str) -> Option<String> {
+    if text.is_ascii() {
+        return None;
+    }
+    DOUBLE_ENCODING_SOURCES.iter().find_map(|name| {
+        let recovered = String::from_utf8(encode_without_bom(text, name)?).ok()?;
+        let multibyte = recovered.chars().filter(|c| c.len_utf8() > 1).count();
+        (multibyte >= DOUBLE_ENCODING_MIN_CHARS && !recovered.contains('\u{FFFD}'))
+            .then_some(recovered)
+    })
 }
 
 /// Convert data from the source encoding to UTF-8 with BOM.
 /// Uses lossy conversion: characters that cannot be decoded are replaced with U+FFFD.
 pub fn convert_to_utf8_bom(data: &[u8], source_encoding_name: &str) -> Result<Vec<u8>, String> {
+    convert_to_utf8(data, source_encoding_name, true)
+}
+
+/// Convert data from the source encoding to UTF-8, optionally prepending a BOM.
+/// Uses lossy conversion: characters that cannot be decoded are replaced with U+FFFD.
+pub fn convert_to_utf8(
+    data: &[u8],
+    source_encoding_name: &str,
+    with_bom: bool,
+) -> Result<Vec<u8>, String> {
+    let options = ConvertOptions {
+        with_bom,
+        ..ConvertOptions::default()
+    };
+    convert_with_options(data, source_encoding_name, &options).map(|output| output.data)
+}
+
+/// Read everything from `reader`, detect its encoding, and write it to `writer` as UTF-8.
+/// Detection needs the whole input, so it is buffered in memory (plus the converted
+/// copy): fine for pipelines of typical text files, not for multi-gigabyte streams.
+/// Uses the default candidate encodings. Binary input is passed through unchanged.
+pub fn convert_stream(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    target_with_bom: bool,
+) -> Result<(), String> {
+    let mut data = Vec::new();
+    reader
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to read input: {}", e))?;
+
+    let converted = match detect_and_convert_bytes(&data, target_with_bom)? {
+        DetectionOutcome::Converted { output, .. } => output.data,
+        // Binary input passes through untouched
+        DetectionOutcome::Binary => data,
+        DetectionOutcome::AlreadyUtf8 { .. } => convert_to_utf8(&data, "UTF-8", target_with_bom)?,
+    };
+    writer
+        .write_all(&converted)
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("Failed to write output: {}", e))
+}
+
+/// Convert data from the source encoding to UTF-8 using the given options.
+/// Line endings are normalized after decoding and before the BOM is written.
+/// In strict mode (`lossy_allowed == false`) malformed input is an error.
+pub fn convert_with_options(
+    data: &[u8],
+    source_encoding_name: &str,
+    options: &ConvertOptions,
+) -> Result<ConvertOutput, String> {
     let source_data = strip_bom(data);
 
-    let encoding = Encoding::for_label(source_encoding_name.as_bytes())
+    // BOM was already stripped above, so decode without BOM sniffing
+    let (decoded, had_errors) = decode_without_bom(source_data, source_encoding_name)?;
+    if had_errors && !options.lossy_allowed {
+        return Err(format!(
+            "Conversion would lose data in encoding {}",
+            canonical_encoding_name(source_encoding_name).unwrap_or(source_encoding_name)
+        ));
+    }
+    let replacement_count = decoded.chars().filter(|&c| c == '\u{FFFD}').count();
+    let round_trip_ok = options.verify_round_trip.then(|| {
+        encode_without_bom(&decoded, source_encoding_name).as_deref() == Some(source_data)
+    });
+
+    // Lossy conversion: allow replacement characters (U+FFFD) instead of failing.
+    // The smart scorer already picked the best encoding, so remaining errors are acceptable;
+    // they are reported back to the caller via replacement_count/lossy.
+
+    let mut text = normalize_line_endings(&decoded, options.line_ending);
+    if options.trim_trailing_whitespace {
+        text = trim_trailing_whitespace(&text);
+    }
+    if let Some(final_newline) = options.ensure_final_newline {
+        text = set_final_newline(&text, final_newline);
+    }
+
+    // Build (optional) UTF-8 BOM + content
+    let mut result = Vec::with_capacity(3 + text.len());
+    if options.with_bom {
+        result.extend_from_slice(&[0xEF, 0xBB, 0xBF]); // UTF-8 BOM
+    }
+    result.extend_from_slice(text.as_bytes());
+
+    Ok(ConvertOutput {
+        data: result,
+        replacement_count,
+        lossy: had_errors,
+        round_trip_ok,
+    })
+}
+
+/// UTF-32 isn't supported by encoding_rs, so these labels are decoded by hand.
+/// Returns whether the encoding is big-endian.
+fn utf32_big_endian(encoding_name: &str) -> Option<bool> {
+    match encoding_name.trim().to_ascii_uppercase().as_str() {
+        "UTF-32LE" | "UTF-32" => Some(false),
+        "UTF-32BE" => Some(true),
+        _ => None,
+    }
+}
+
+/// The canonical name of a supported encoding label, or None if unknown.
+pub fn canonical_encoding_name(encoding_name: &str) -> Option<&'static str> {
+    match utf32_big_endian(encoding_name) {
+        Some(true) => Some("UTF-32BE"),
+        Some(false) => Some("UTF-32LE"),
+        None => Encoding::for_label(encoding_name.trim().as_bytes()).map(|e| e.name()),
+    }
+}
+
+/// Decode BOM-less data from the given encoding. Returns the text and whether
+/// malformed input was replaced with U+FFFD.
+pub fn decode_without_bom(data: &[u8], encoding_name: &str) -> Result<(String, bool), String> {
+    if let Some(big_endian) = utf32_big_endian(encoding_name) {
+        return Ok(decode_utf32(data, big_endian));
+    }
+
+    let encoding = Encoding::for_label(encoding_name.trim().as_bytes())
+        .ok_or_else(|| format!("Unknown encoding: {}", encoding_name))?;
+    let (decoded, had_errors) = encoding.decode_without_bom_handling(data);
+    Ok((decoded.into_owned(), had_errors))
+}
+
+/// Encode `text` in `encoding_name` without a BOM, the inverse of `decode_without_bom`.
+/// Returns None for unknown labels or text the encoding can't represent.
+pub fn encode_without_bom(text: &str, encoding_name: &str) -> Option<Vec<u8>> {
+    if let Some(big_endian) = utf32_big_endian(encoding_name) {
+        let to_bytes = if big_endian { u32::to_be_bytes } else { u32::to_le_bytes };
+        return Some(text.chars().flat_map(|c| to_bytes(c as u32)).collect());
+    }
+
+    let encoding = Encoding::for_label(encoding_name.trim().as_bytes())?;
+    // encoding_rs only encodes to ASCII-compatible encodings, so UTF-16 is done by hand
+    if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+        let to_bytes = if encoding == encoding_rs::UTF_16BE {
+            u16::to_be_bytes
+        } else {
+            u16::to_le_bytes
+        };
+        return Some(text.encode_utf16().flat_map(to_bytes).collect());
+    }
+
+    let (encoded, _, had_errors) = encoding.encode(text);
+    (!had_errors).then(|| encoded.into_owned())
+}
+
+/// Encode `text` in `encoding_name` without a BOM. Unlike `Encoding::encode`, which
+/// writes unmappable characters as HTML numeric entities (`&#8364;`), they become `?`
+/// so the output stays plain text, and they are counted.
+pub fn encode_with_replacement(text: &str, encoding_name: &str) -> Result<EncodeOutput, String> {
+    let encoding = Encoding::for_label(encoding_name.trim().as_bytes());
+    // Unicode targets represent everything (and encoding_rs only encodes UTF-16 as UTF-8)
+    let is_unicode = utf32_big_endian(encoding_name).is_some()
+        || encoding.is_some_and(|e| e.output_encoding() == encoding_rs::UTF_8);
+    if is_unicode {
+        let data = encode_without_bom(text, encoding_name)
+            .ok_or_else(|| format!("Unknown encoding: {}", encoding_name))?;
+        return Ok(EncodeOutput {
+            data,
+            unmappable_count: 0,
+        });
+    }
+
+    let encoding = encoding.ok_or_else(|| format!("Unknown encoding: {}", encoding_name))?;
+    let mut encoder = encoding.new_encoder();
+    let mut data = Vec::with_capacity(text.len() + 16);
+    let mut unmappable_count = 0;
+    let mut rest = text;
+    loop {
+        let (result, read) =
+            encoder.encode_from_utf8_to_vec_without_replacement(rest, &mut data, true);
+        rest = &rest[read..];
+        match result {
+            EncoderResult::InputEmpty => break,
+            EncoderResult::OutputFull => data.reserve(rest.len() + 16),
+            EncoderResult::Unmappable(_) => {
+                unmappable_count += 1;
+                // Through the encoder, so stateful encodings (ISO-2022-JP) switch back to
+                // ASCII; an escape sequence plus `?` always fits in the reserved space
+                data.reserve(8);
+                let _ = encoder.encode_from_utf8_to_vec_without_replacement("?", &mut data, false);
+            }
+        }
+    }
+    Ok(EncodeOutput {
+        data,
+        unmappable_count,
+    })
+}
+
+/// Decode for display: a BOM in the data wins over `encoding_name` (like encoding_rs's
+/// `decode`), and unknown labels fall back to UTF-8.
+pub fn decode_for_display(data: &[u8], encoding_name: &str) -> String {
+    let (name, bom_len) = match detect_bom(data) {
+        Some((bom_name, len)) => (bom_name, len),
+        None => (canonical_encoding_name(encoding_name).unwrap_or("UTF-8"), 0),
+    };
+    decode_without_bom(&data[bom_len..], name)
+        .map(|(text, _)| text)
+        .unwrap_or_default()
+}
+
+fn decode_utf32(data: &[u8], big_endian: bool) -> (String, bool) {
+    let mut text = String::with_capacity(data.len() / 4);
+    let mut had_errors = false;
+    for unit in data.chunks(4) {
+        let ch = <[u8; 4]>::try_from(unit)
+            .ok()
+            .map(|bytes| {
+                if big_endian {
+                    u32::from_be_bytes(bytes)
+                } else {
+                    u32::from_le_bytes(bytes)
+                }
+            })
+            .and_then(char::from_u32);
+        match ch {
+            Some(ch) => text.push(ch),
+            None => {
+                // Invalid scalar value or truncated trailing unit
+                had_errors = true;
+                text.push('\u{FFFD}');
+            }
+        }
+    }
+    (text, had_errors)
+}
+
+/// Classify the line breaks in `text`. CRLF counts as one break, not a CR plus an LF.
+pub fn detect_line_ending(text: &str) -> LineEndingKind {
+    let (mut lf, mut crlf, mut cr) = (false, false, false);
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\r' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                crlf = true;
+            }
+            '\r' => cr = true,
+            '\n' => lf = true,
+            _ => {}
+        }
+    }
+
+    match (lf, crlf, cr) {
+        (false, false, false) => LineEndingKind::None,
+        (true, false, false) => LineEndingKind::Lf,
+        (false, true, false) => LineEndingKind::Crlf,
+        (false, false, true) => LineEndingKind::Cr,
+        _ => LineEndingKind::Mixed,
+    }
+}
+
+/// Remove spaces and tabs at the end of each line, keeping the line breaks as they are.
+pub fn trim_trailing_whitespace(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(['\r', '\n']) {
+        result.push_str(rest[..pos].trim_end_matches([' ', '\t']));
+        let break_len = if rest[pos..].starts_with("\r\n") { 2 } else { 1 };
+        result.push_str(&rest[pos..pos + break_len]);
+        rest = &rest[pos + break_len..];
+    }
+    result.push_str(rest.trim_end_matches([' ', '\t']));
+    result
+}
+
+/// Make `text` end with exactly one line break (`final_newline`) or none at all.
+/// Trailing blank lines are dropped either way. An added break uses the text's own
+/// style (CRLF or CR when that's all it uses, LF otherwise). Empty text stays empty.
+pub fn set_final_newline(text: &str, final_newline: bool) -> String {
+    final_newline_in_style(text, final_newline, detect_line_ending(text))
+}
+
+/// `set_final_newline` with the line-ending style given rather than detected in `text`.
+fn final_newline_in_style(text: &str, final_newline: bool, style: LineEndingKind) -> String {
+    let body = text.trim_end_matches(['\r', '\n']);
+    if !final_newline || body.is_empty() {
+        return body.to_string();
+    }
+
+    let newline = match style {
+        LineEndingKind::Crlf => "\r\n",
+        LineEndingKind::Cr => "\r",
+        _ => "\n",
+    };
+    format!("{}{}", body, newline)
+}
+
+/// Normalize line breaks (CRLF, LF, and lone CR) to the requested style.
+/// Never adds a trailing newline that wasn't already there.
+pub fn normalize_line_endings(text: &str, mode: LineEnding) -> String {
+    let newline = match mode {
+        LineEnding::Preserve => return text.to_string(),
+        LineEnding::Lf => "\n",
+        LineEnding::Crlf => "\r\n",
+    };
+
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\r' => {
+                // CRLF counts as one break; a lone CR (old Mac) is a break on its own
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                result.push_str(newline);
+            }
+            '\n' => result.push_str(newline),
+            _ => result.push(ch),
+        }
+    }
+    result
+}
+
+/// Bytes read per step by `convert_chunked`.
+pub const CONVERT_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Convert everything from `reader` (in `source_encoding_name`) to UTF-8 into `writer`
+/// one chunk at a time, so huge files are never held in memory whole: only the current
+/// chunk, plus the unfinished last line when line endings or whitespace are rewritten.
+/// The streaming decoder carries multibyte characters split across chunks, so the
+/// output matches `convert_with_options` on the whole input. `on_progress` gets the
+/// input bytes processed after every chunk. UTF-32 isn't supported here.
+pub fn convert_chunked(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    source_encoding_name: &str,
+    options: &ConvertOptions,
+    mut on_progress: impl FnMut(u64),
+) -> Result<ChunkedOutput, String> {
+    let encoding = Encoding::for_label(source_encoding_name.trim().as_bytes())
         .ok_or_else(|| format!("Unknown encoding: {}", source_encoding_name))?;
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    // These options work on whole lines, so an unfinished line waits for the next chunk
+    let rewrites_lines = options.line_ending != LineEnding::Preserve
+        || options.trim_trailing_whitespace
+        || options.ensure_final_newline.is_some();
+    let write_error = |e: io::Error| format!("Failed to write output: {}", e);
 
-    let (decoded, _, _had_errors) = encoding.decode(source_data);
+    if options.with_bom {
+        writer.write_all(&[0xEF, 0xBB, 0xBF]).map_err(write_error)?;
+    }
 
-    // Lossy conversion: allow replacement characters (U+FFFD) instead of failing.
-    // The smart scorer already picked the best encoding, so remaining errors are acceptable.
+    let mut input = vec![0u8; CONVERT_CHUNK_SIZE];
+    let mut decoded = String::new();
+    let mut pending = String::new();
+    let mut line_ending = LineEndingKind::None;
+    let mut processed = 0u64;
+    let mut replacement_count = 0;
+    let mut had_errors = false;
+    loop {
+        let read = read_chunk(&mut reader, &mut input)
+            .map_err(|e| format!("Failed to read input: {}", e))?;
+        let last = read < input.len();
+        let mut chunk = if processed == 0 {
+            strip_bom(&input[..read])
+        } else {
+            &input[..read]
+        };
+        processed += read as u64;
 
-    // Build UTF-8 BOM + content
-    let mut result = Vec::with_capacity(3 + decoded.len());
-    result.extend_from_slice(&[0xEF, 0xBB, 0xBF]); // UTF-8 BOM
-    result.extend_from_slice(decoded.as_bytes());
+        decoded.clear();
+        loop {
+            decoded.reserve(
+                decoder
+                    .max_utf8_buffer_length(chunk.len())
+                    .unwrap_or(chunk.len() * 3),
+            );
+            let (result, consumed, errors) = decoder.decode_to_string(chunk, &mut decoded, last);
+            had_errors |= errors;
+            chunk = &chunk[consumed..];
+            if result == CoderResult::InputEmpty {
+                break;
+            }
+        }
+        if had_errors && !options.lossy_allowed {
+            return Err(format!(
+                "Conversion would lose data in encoding {}",
+                encoding.name()
+            ));
+        }
+        replacement_count += decoded.chars().filter(|&c| c == '\u{FFFD}').count();
+
+        if rewrites_lines {
+            pending.push_str(&decoded);
+            let split = if last {
+                pending.len()
+            } else {
+                complete_lines_len(&pending)
+            };
+            let mut text = normalize_line_endings(&pending[..split], options.line_ending);
+            if options.trim_trailing_whitespace {
+                text = trim_trailing_whitespace(&text);
+            }
+            line_ending = combine_line_endings(line_ending, detect_line_ending(&text));
+            if let (true, Some(final_newline)) = (last, options.ensure_final_newline) {
+                text = final_newline_in_style(&text, final_newline, line_ending);
+            }
+            writer.write_all(text.as_bytes()).map_err(write_error)?;
+            pending.drain(..split);
+        } else {
+            writer.write_all(decoded.as_bytes()).map_err(write_error)?;
+        }
 
-    Ok(result)
+        on_progress(processed);
+        if last {
+            break;
+        }
+    }
+    writer.flush().map_err(write_error)?;
+
+    Ok(ChunkedOutput {
+        replacement_count,
+        lossy: had_errors,
+    })
+}
+
+/// Whether `convert_chunked` can decode `encoding_name` (everything but UTF-32).
+pub fn can_convert_chunked(encoding_name: &str) -> bool {
+    Encoding::for_label(encoding_name.trim().as_bytes()).is_some()
+}
+
+/// Fill `buf` from `reader`, stopping short only at the end of the input.
+fn read_chunk(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Length of the leading complete lines of `text` that are followed by more content.
+/// Trailing line breaks stay behind with the last line, where the final-newline
+/// option needs them, and a CR is never split from the LF after it.
+fn complete_lines_len(text: &str) -> usize {
+    let content_end = text.trim_end_matches(['\r', '\n']).len();
+    text[..content_end]
+        .rfind(['\r', '\n'])
+        .map_or(0, |pos| pos + 1)
+}
+
+/// Line-ending style of two pieces of text put together.
+fn combine_line_endings(a: LineEndingKind, b: LineEndingKind) -> LineEndingKind {
+    match (a, b) {
+        (LineEndingKind::None, kind) | (kind, LineEndingKind::None) => kind,
+        (a, b) if a == b => a,
+        _ => LineEndingKind::Mixed,
+    }
 }
 
 /// Strip BOM from the beginning of data if present.
 fn strip_bom(data: &[u8]) -> &[u8] {
-    if data.len() >= 3 && data[0] == 0xEF && data[1] == 0xBB && data[2] == 0xBF {
-        return &data[3..];
-    }
-    if data.len() >= 2 {
-        if (data[0] == 0xFF && data[1] == 0xFE) || (data[0] == 0xFE && data[1] == 0xFF) {
-            return &data[2..];
-        }
+    match detect_bom(data) {
+        Some((_, len)) => &data[len..],
+        None => data,
     }
-    data
 }
 
 /// Read file contents as raw bytes (read-only, never modifies the original).
@@ -153,21 +1015,147 @@ pub fn read_file_bytes(path: &Path) -> io::Result<Vec<u8>> {
 }
 
 /// Get the first N lines from decoded text for preview.
-pub fn get_preview_lines(text: &str, n: usize) -> Vec<String> {
-    text.lines().take(n).map(|s| s.to_string()).collect()
+/// Lines are made safe to render: control characters are replaced with visible
+/// glyphs (tabs are kept) and lines longer than `max_width` characters are cut
+/// with an ellipsis. `max_width` 0 means no limit.
+pub fn get_preview_lines(text: &str, n: usize, max_width: usize) -> Vec<String> {
+    text.lines()
+        .take(n)
+        .map(|line| sanitize_preview_line(line, max_width))
+        .collect()
 }
 
+fn sanitize_preview_line(line: &str, max_width: usize) -> String {
+    let mut out = String::new();
+    for (i, ch) in line.chars().enumerate() {
+        if max_width > 0 && i >= max_width {
+            out.push('…');
+            break;
+        }
+        out.push(visible_char(ch));
+    }
+    out
+}
+
+/// Map control characters to Unicode "Control Pictures" so they show up in the UI.
+fn visible_char(ch: char) -> char {
+    match ch {
+        '\t' => ch,
+        '\u{00}'..='\u{1F}' => char::from_u32(0x2400 + ch as u32).unwrap_or('\u{2426}'),
+        '\u{7F}' => '\u{2421}',
+        _ if ch.is_control() => '\u{2426}',
+        _ => ch,
+    }
+}
+
+/// Display metadata for a supported encoding.
+#[derive(Debug, Clone, Serialize)]
+pub struct EncodingInfo {
+    /// Label accepted by the convert commands.
+    pub label: &'static str,
+    /// Dropdown text, e.g. "Shift_JIS (日本語)".
+    pub display_name: &'static str,
+    /// Other common names users search for (CP932, Latin-1, ...).
+    pub aliases: &'static [&'static str],
+    pub language: &'static str,
+}
+
+/// Supported encodings in dropdown order.
+const ENCODING_INFO: &[EncodingInfo] = &[
+    EncodingInfo {
+        label: "Shift_JIS",
+        display_name: "Shift_JIS (日本語)",
+        aliases: &["SJIS", "CP932", "Windows-31J", "MS932"],
+        language: "日本語",
+    },
+    EncodingInfo {
+        label: "UTF-8",
+        display_name: "UTF-8 (Unicode)",
+        aliases: &["UTF8"],
+        language: "Unicode",
+    },
+    EncodingInfo {
+        label: "EUC-JP",
+        display_name: "EUC-JP (日本語)",
+        aliases: &["eucJP", "x-euc-jp"],
+        language: "日本語",
+    },
+    EncodingInfo {
+        label: "ISO-2022-JP",
+        display_name: "ISO-2022-JP (日本語 / JIS)",
+        aliases: &["JIS", "csISO2022JP"],
+        language: "日本語",
+    },
+    EncodingInfo {
+        label: "UTF-16LE",
+        display_name: "UTF-16LE (Unicode)",
+        aliases: &["UTF-16", "UCS-2"],
+        language: "Unicode",
+    },
+    EncodingInfo {
+        label: "UTF-16BE",
+        display_name: "UTF-16BE (Unicode)",
+        aliases: &["UnicodeFFFE"],
+        language: "Unicode",
+    },
+    EncodingInfo {
+        label: "UTF-32LE",
+        display_name: "UTF-32LE (Unicode)",
+        aliases: &["UTF-32", "UCS-4"],
+        language: "Unicode",
+    },
+    EncodingInfo {
+        label: "UTF-32BE",
+        display_name: "UTF-32BE (Unicode)",
+        aliases: &[],
+        language: "Unicode",
+    },
+    EncodingInfo {
+        label: "GBK",
+        display_name: "GBK (簡体字中国語)",
+        aliases: &["GB2312", "CP936"],
+        language: "簡体字中国語",
+    },
+    EncodingInfo {
+        label: "Big5",
+        display_name: "Big5 (繁体字中国語)",
+        aliases: &["CP950", "Big5-HKSCS"],
+        language: "繁体字中国語",
+    },
+    EncodingInfo {
+        label: "windows-1252",
+        display_name: "windows-1252 (西ヨーロッパ言語)",
+        aliases: &["CP1252", "Latin-1", "ISO-8859-1"],
+        language: "西ヨーロッパ言語",
+    },
+    EncodingInfo {
+        label: "ISO-8859-15",
+        display_name: "ISO-8859-15 (西ヨーロッパ言語 / ユーロ記号対応)",
+        aliases: &["Latin-9"],
+        language: "西ヨーロッパ言語",
+    },
+    EncodingInfo {
+        label: "windows-1250",
+        display_name: "windows-1250 (中央ヨーロッパ言語)",
+        aliases: &["CP1250"],
+        language: "中央ヨーロッパ言語",
+    },
+    EncodingInfo {
+        label: "windows-1251",
+        display_name: "windows-1251 (キリル文字)",
+        aliases: &["CP1251"],
+        language: "キリル文字",
+    },
+];
+
 /// Get a list of supported encoding names for the UI dropdown.
 pub fn supported_encodings() -> Vec<&'static str> {
-    vec![
-        "Shift_JIS",
-        "UTF-8",
-        "EUC-JP",
-        "ISO-2022-JP",
-        "UTF-16LE",
-        "UTF-16BE",
-        "windows-1252",
-    ]
+    ENCODING_INFO.iter().map(|info| info.label).collect()
+}
+
+/// Supported encodings with display names, aliases, and languages.
+pub fn supported_encodings_detailed() -> Vec<EncodingInfo> {
+    ENCODING_INFO.to_vec()
 }
 
 /// Binary file extensions that should be opened directly without encoding conversion.
@@ -183,8 +1171,25 @@ const BINARY_EXTENSIONS: &[&str] = &[
     "exe", "dll", "msi",               // Executables
 ];
 
+/// Number of leading bytes sampled for magic-byte and NUL-byte detection.
+const BINARY_SAMPLE_LEN: usize = 8192;
+
+/// A NUL anywhere in this many leading bytes marks the file as binary.
+const NUL_STRICT_PREFIX_LEN: usize = 1024;
+
+/// Above this ratio of NUL bytes in the sample, the file is treated as binary.
+const NUL_RATIO_THRESHOLD: f64 = 0.01;
+
+/// Read at most `len` bytes from the start of a file.
+pub fn read_file_prefix(path: &Path, len: usize) -> io::Result<Vec<u8>> {
+    let file = File::open(path)?;
+    let mut data = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut data)?;
+    Ok(data)
+}
+
 /// Check if a file is a binary format that should be opened directly (pass-through).
-/// Detection is done by file extension and magic bytes.
+/// Detection is done by file extension, magic bytes, and finally a NUL-byte heuristic.
 pub fn is_binary_file(path: &Path) -> bool {
     // Check by extension
     if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
@@ -194,23 +1199,635 @@ pub fn is_binary_file(path: &Path) -> bool {
         }
     }
 
-    // Check by magic bytes (first 8 bytes)
-    if let Ok(data) = fs::read(path) {
-        if data.len() >= 8 {
-            // ZIP signature (xlsx, docx, pptx are ZIP-based)
-            if data[0] == 0x50 && data[1] == 0x4B && data[2] == 0x03 && data[3] == 0x04 {
-                return true;
+    // Sample the head of the file without reading the whole thing
+    let data = match read_file_prefix(path, BINARY_SAMPLE_LEN) {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+
+    is_binary_data(&data)
+}
+
+/// Content-only binary check (magic bytes, then the NUL heuristic) for data that
+/// has no path. Only the first `BINARY_SAMPLE_LEN` bytes are looked at.
+pub fn is_binary_data(data: &[u8]) -> bool {
+    let sample = &data[..data.len().min(BINARY_SAMPLE_LEN)];
+    magic_signature(sample).is_some() || has_binary_nul_bytes(sample)
+}
+
+/// Leading bytes of common binary formats, checked before the NUL heuristic.
+const MAGIC_SIGNATURES: &[(&[u8], &str)] = &[
+    (&[0x50, 0x4B, 0x03, 0x04], "ZIP"), // also xlsx, docx, pptx
+    (&[0xD0, 0xCF, 0x11, 0xE0], "OLE2"), // xls, doc, ppt
+    (&[0x25, 0x50, 0x44, 0x46], "PDF"),
+    (&[0x89, 0x50, 0x4E, 0x47], "PNG"),
+    (&[0x47, 0x49, 0x46, 0x38], "GIF"),
+    (&[0xFF, 0xD8, 0xFF], "JPEG"),
+    (&[0x42, 0x4D], "BMP"),
+    (&[0x52, 0x49, 0x46, 0x46], "RIFF"), // WAV, AVI, WebP
+    (&[0x49, 0x44, 0x33], "MP3"),        // ID3 tag
+    (&[0x1F, 0x8B], "GZIP"),
+    (&[0x37, 0x7A, 0xBC, 0xAF], "7z"),
+    (&[0x52, 0x61, 0x72, 0x21], "RAR"),
+];
+
+/// Name of the binary format whose signature `data` starts with, if any.
+pub fn magic_signature(data: &[u8]) -> Option<&'static str> {
+    MAGIC_SIGNATURES
+        .iter()
+        .find(|(signature, name)| data.starts_with(signature) && signature_confirmed(data, name))
+        .map(|&(_, name)| name)
+}
+
+/// Extra checks for signatures short enough to collide with ordinary text.
+/// "BM" would catch any CSV starting with "BMW", so BMP also needs the header's
+/// reserved bytes (offsets 6..10) to be zero.
+fn signature_confirmed(data: &[u8], name: &str) -> bool {
+    match name {
+        "BMP" => data.get(6..10) == Some(&[0, 0, 0, 0]),
+        _ => true,
+    }
+}
+
+/// NUL-byte heuristic for binaries without a known extension or signature.
+/// UTF-16/32 text legitimately contains NULs, so BOM-marked UTF-16/32 and
+/// BOM-less UTF-16 with the alternating NUL pattern are never flagged.
+fn has_binary_nul_bytes(sample: &[u8]) -> bool {
+    let wide_bom = detect_bom(sample).is_some_and(|(name, _)| name != "UTF-8");
+    if wide_bom {
+        return false;
+    }
+    if detect_bomless_utf16(sample).is_some() {
+        return false;
+    }
+
+    let strict_len = sample.len().min(NUL_STRICT_PREFIX_LEN);
+    if sample[..strict_len].contains(&0) {
+        return true;
+    }
+
+    let nul_count = sample.iter().filter(|&&b| b == 0).count();
+    !sample.is_empty() && nul_count as f64 / sample.len() as f64 > NUL_RATIO_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    fn shift_jis(text: &str) -> Vec<u8> {
+        encoding_rs::SHIFT_JIS.encode(text).0.into_owned()
+    }
+
+    #[test]
+    fn target_encoding_controls_bom() {
+        let data = shift_jis("日本語のテキスト");
+        let with_bom = |target: &str| {
+            AppConfig {
+                target_encoding: target.to_string(),
+                ..AppConfig::default()
             }
-            // OLE2 Compound Document (xls, doc, ppt)
-            if data[0] == 0xD0 && data[1] == 0xCF && data[2] == 0x11 && data[3] == 0xE0 {
-                return true;
+            .target_with_bom()
+        };
+
+        let plain = convert_to_utf8(&data, "Shift_JIS", with_bom("utf-8")).unwrap();
+        assert_eq!(plain, "日本語のテキスト".as_bytes());
+
+        let bom = convert_to_utf8(&data, "Shift_JIS", with_bom("utf-8-bom")).unwrap();
+        assert_eq!(&bom[..3], &[0xEF, 0xBB, 0xBF]);
+        assert_eq!(&bom[3..], "日本語のテキスト".as_bytes());
+    }
+
+    #[test]
+    fn line_endings_are_normalized() {
+        let mixed = "a\r\nb\nc\rd";
+        assert_eq!(normalize_line_endings(mixed, LineEnding::Lf), "a\nb\nc\nd");
+        assert_eq!(
+            normalize_line_endings(mixed, LineEnding::Crlf),
+            "a\r\nb\r\nc\r\nd"
+        );
+        assert_eq!(normalize_line_endings(mixed, LineEnding::Preserve), mixed);
+        // A trailing lone CR is still one break; no newline is added at the end
+        assert_eq!(normalize_line_endings("x\r", LineEnding::Crlf), "x\r\n");
+        assert_eq!(normalize_line_endings("x", LineEnding::Lf), "x");
+
+        let options = ConvertOptions {
+            with_bom: false,
+            line_ending: LineEnding::Lf,
+            ..ConvertOptions::default()
+        };
+        let converted = convert_with_options(&shift_jis("行1\r\n行2\r行3"), "Shift_JIS", &options);
+        assert_eq!(converted.unwrap().data, "行1\n行2\n行3".as_bytes());
+    }
+
+    fn assert_sorted_descending(scores: &[scorer::EncodingScore]) {
+        for pair in scores.windows(2) {
+            assert!(
+                pair[0].score >= pair[1].score,
+                "{} ({}) ranked above {} ({})",
+                pair[0].encoding_name,
+                pair[0].score,
+                pair[1].encoding_name,
+                pair[1].score
+            );
+        }
+    }
+
+    #[test]
+    fn detection_report_is_sorted_by_score() {
+        let candidates = AppConfig::default().candidate_encodings;
+        let report = detection_report(
+            &shift_jis("テスト用のファイルです。"),
+            &candidates,
+            &scorer::ScoringWeights::default(),
+        );
+        assert!(report.len() > 1);
+        assert_eq!(report[0].encoding_name, "Shift_JIS");
+        assert_sorted_descending(&report);
+
+        // The BOM short-circuit shows up as a top entry with score 1.0
+        let mut bom = vec![0xEF, 0xBB, 0xBF];
+        bom.extend_from_slice("日本語".as_bytes());
+        let report = detection_report(&bom, &candidates, &scorer::ScoringWeights::default());
+        assert_eq!(report[0].encoding_name, "UTF-8");
+        assert_eq!(report[0].score, 1.0);
+        assert_sorted_descending(&report);
+    }
+
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("encodingman_encoder_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn binary_check_reads_only_the_prefix() {
+        let dir = test_dir("magic_prefix");
+        let mut pdf = b"%PDF-1.7\n".to_vec();
+        pdf.resize(64 * 1024, b'a');
+        let pdf_path = dir.join("report");
+        fs::write(&pdf_path, &pdf).unwrap();
+        assert_eq!(
+            read_file_prefix(&pdf_path, BINARY_SAMPLE_LEN)
+                .unwrap()
+                .len(),
+            BINARY_SAMPLE_LEN
+        );
+        assert!(is_binary_file(&pdf_path));
+
+        // A signature past the prefix is never seen
+        let mut text = vec![b'a'; 64 * 1024];
+        text.extend_from_slice(b"PK\x03\x04\x14\x00\x00\x00");
+        let text_path = dir.join("notes.txt");
+        fs::write(&text_path, &text).unwrap();
+        assert!(!is_binary_file(&text_path));
+    }
+
+    #[test]
+    fn nul_heuristic_boundaries() {
+        // Any NUL in the first 1024 bytes
+        let mut head = vec![b'a'; BINARY_SAMPLE_LEN];
+        head[NUL_STRICT_PREFIX_LEN - 1] = 0;
+        assert!(has_binary_nul_bytes(&head));
+
+        // Past that, only a NUL ratio above 1% counts
+        let sample_with_nuls = |count: usize| {
+            let mut sample = vec![b'a'; BINARY_SAMPLE_LEN];
+            for byte in sample.iter_mut().skip(NUL_STRICT_PREFIX_LEN).take(count) {
+                *byte = 0;
             }
-            // PDF signature
-            if data[0] == 0x25 && data[1] == 0x50 && data[2] == 0x44 && data[3] == 0x46 {
-                return true;
+            sample
+        };
+        let limit = (BINARY_SAMPLE_LEN as f64 * NUL_RATIO_THRESHOLD) as usize;
+        assert!(!has_binary_nul_bytes(&sample_with_nuls(limit)));
+        assert!(has_binary_nul_bytes(&sample_with_nuls(limit + 1)));
+
+        // BOM-marked UTF-16 is full of NULs but still text
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend("text".encode_utf16().flat_map(u16::to_le_bytes));
+        assert!(!has_binary_nul_bytes(&utf16));
+    }
+
+    #[test]
+    fn file_of_nuls_is_binary_but_utf8_text_is_not() {
+        let dir = test_dir("nul_files");
+        let nuls = dir.join("blob");
+        fs::write(&nuls, vec![0u8; 4096]).unwrap();
+        assert!(is_binary_file(&nuls));
+
+        let text = dir.join("notes");
+        fs::write(&text, "UTF-8のテキストファイルです。\n".repeat(200)).unwrap();
+        assert!(!is_binary_file(&text));
+    }
+
+    #[test]
+    fn wrong_encoding_reports_replacements() {
+        let data = shift_jis("日本語のテキスト");
+        let options = ConvertOptions::default();
+
+        let wrong = convert_with_options(&data, "UTF-8", &options).unwrap();
+        assert!(wrong.replacement_count > 0);
+        assert!(wrong.lossy);
+
+        let right = convert_with_options(&data, "Shift_JIS", &options).unwrap();
+        assert_eq!(right.replacement_count, 0);
+        assert!(!right.lossy);
+    }
+
+    #[test]
+    fn strict_mode_rejects_malformed_input() {
+        let data = b"abc\xFFdef";
+        let strict = ConvertOptions {
+            lossy_allowed: false,
+            ..ConvertOptions::default()
+        };
+        assert!(convert_with_options(data, "UTF-8", &strict).is_err());
+
+        let lenient = convert_with_options(data, "UTF-8", &ConvertOptions::default()).unwrap();
+        assert_eq!(lenient.replacement_count, 1);
+
+        // Well-formed input converts the same in both modes
+        let sjis = shift_jis("日本語");
+        assert_eq!(
+            convert_with_options(&sjis, "Shift_JIS", &strict)
+                .unwrap()
+                .data,
+            convert_with_options(&sjis, "Shift_JIS", &ConvertOptions::default())
+                .unwrap()
+                .data
+        );
+    }
+
+    fn utf16_without_bom(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| {
+                if big_endian {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bomless_utf16_is_detected() {
+        let text = "id,name,city\r\n1,Yamada,Tokyo\r\n2,Suzuki,Osaka\r\n";
+
+        let le = detect_bomless_utf16(&utf16_without_bom(text, false)).unwrap();
+        assert_eq!(le.encoding_name, "UTF-16LE");
+        assert!(le.confidence > 0.9);
+
+        let be = detect_bomless_utf16(&utf16_without_bom(text, true)).unwrap();
+        assert_eq!(be.encoding_name, "UTF-16BE");
+        assert!(!is_already_utf8(&utf16_without_bom(text, true)));
+
+        let candidates = AppConfig::default().candidate_encodings;
+        let report = detection_report(
+            &utf16_without_bom(text, false),
+            &candidates,
+            &scorer::ScoringWeights::default(),
+        );
+        assert!(report.iter().any(|s| s.encoding_name == "UTF-16LE"));
+        assert_sorted_descending(&report);
+    }
+
+    #[test]
+    fn ascii_with_occasional_nuls_is_not_utf16() {
+        let mut data = b"id,name,city\r\n1,Yamada,Tokyo\r\n2,Suzuki,Osaka\r\n".repeat(4);
+        for i in (0..data.len()).step_by(17) {
+            data[i] = 0;
+        }
+
+        assert!(detect_bomless_utf16(&data).is_none());
+
+        // NULs lined up in odd positions, but far too few for UTF-16LE
+        let mut data = b"id,name,city\r\n1,Yamada,Tokyo\r\n2,Suzuki,Osaka\r\n".repeat(4);
+        for i in (1..data.len()).step_by(6) {
+            data[i] = 0;
+        }
+        assert!(detect_bomless_utf16(&data).is_none());
+
+        assert!(detect_bomless_utf16(b"plain ASCII text without any NUL bytes").is_none());
+    }
+
+    #[test]
+    fn preview_lines_are_sanitized_and_truncated() {
+        let lines = get_preview_lines("a\0b\x1B[0m\tc\x7F\nabcdefgh\nthird", 2, 5);
+        assert_eq!(lines, vec!["a\u{2400}b\u{241B}[…", "abcde…"]);
+
+        let lines = get_preview_lines("a\tb\u{85}\nabcdefgh", 10, 0);
+        assert_eq!(lines, vec!["a\tb\u{2426}", "abcdefgh"]);
+    }
+
+    #[test]
+    fn stream_is_converted_to_utf8() {
+        let input = shift_jis("名前,住所\r\nやまだ,とうきょう\r\n");
+        let mut output = Vec::new();
+        convert_stream(&input[..], &mut output, false).unwrap();
+        assert_eq!(output, "名前,住所\r\nやまだ,とうきょう\r\n".as_bytes());
+
+        let mut output = Vec::new();
+        convert_stream(&b"plain"[..], &mut output, true).unwrap();
+        assert_eq!(output, b"\xEF\xBB\xBFplain");
+    }
+
+    #[test]
+    fn utf32le_bom_is_not_taken_for_utf16le() {
+        assert_eq!(
+            detect_bom(&[0xFF, 0xFE, 0x00, 0x00, 0x41, 0x00, 0x00, 0x00]),
+            Some(("UTF-32LE", 4))
+        );
+        assert_eq!(detect_bom(&[0xFF, 0xFE, 0x41, 0x00]), Some(("UTF-16LE", 2)));
+        assert_eq!(detect_bom(&[0x00, 0x00, 0xFE, 0xFF]), Some(("UTF-32BE", 4)));
+        assert_eq!(detect_bom(&[0xFE, 0xFF, 0x00, 0x41]), Some(("UTF-16BE", 2)));
+        assert_eq!(detect_bom(b"plain"), None);
+    }
+
+    #[test]
+    fn line_endings_are_classified() {
+        assert_eq!(detect_line_ending("no breaks"), LineEndingKind::None);
+        assert_eq!(detect_line_ending("a\nb\n"), LineEndingKind::Lf);
+        assert_eq!(detect_line_ending("a\r\nb\r\n"), LineEndingKind::Crlf);
+        assert_eq!(detect_line_ending("a\rb\r"), LineEndingKind::Cr);
+        assert_eq!(detect_line_ending("a\r\nb\n"), LineEndingKind::Mixed);
+    }
+
+    #[test]
+    fn round_trip_is_checked_only_when_asked() {
+        let options = ConvertOptions {
+            verify_round_trip: true,
+            ..ConvertOptions::default()
+        };
+        let data = shift_jis("やまだ,とうきょう\n");
+        let output = convert_with_options(&data, "Shift_JIS", &options).unwrap();
+        assert_eq!(output.round_trip_ok, Some(true));
+
+        // The lone lead byte becomes U+FFFD, which can't encode back
+        let output = convert_with_options(b"abc\x82", "Shift_JIS", &options).unwrap();
+        assert_eq!(output.round_trip_ok, Some(false));
+
+        let output = convert_with_options(&data, "Shift_JIS", &ConvertOptions::default()).unwrap();
+        assert_eq!(output.round_trip_ok, None);
+    }
+
+    #[test]
+    fn magic_signatures_are_recognized() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"PK\x03\x04rest", "ZIP"),
+            (b"\xD0\xCF\x11\xE0rest", "OLE2"),
+            (b"%PDF-1.7", "PDF"),
+            (b"\x89PNG\r\n\x1A\n", "PNG"),
+            (b"GIF89a", "GIF"),
+            (b"\xFF\xD8\xFF\xE0", "JPEG"),
+            (b"BM\x36\x00\x0C\x00\x00\x00\x00\x00", "BMP"),
+            (b"RIFF\x24\x00\x00\x00WAVE", "RIFF"),
+            (b"ID3\x04\x00", "MP3"),
+            (b"\x1F\x8B\x08\x00", "GZIP"),
+            (b"7z\xBC\xAF\x27\x1C", "7z"),
+            (b"Rar!\x1A\x07", "RAR"),
+        ];
+        for (data, name) in cases {
+            assert_eq!(magic_signature(data), Some(*name));
+        }
+    }
+
+    #[test]
+    fn bmp_needs_zero_reserved_bytes() {
+        assert_eq!(magic_signature(b"BMW,Audi,VW\n"), None);
+        assert_eq!(magic_signature(b"BM"), None);
+        assert_eq!(magic_signature(b"plain,text\n"), None);
+    }
+
+    #[test]
+    fn utf8_bom_gets_its_own_label() {
+        assert_eq!(utf8_display_name(b"\xEF\xBB\xBFa,b"), "UTF-8 (BOM)");
+        assert_eq!(utf8_display_name(b"a,b"), "UTF-8");
+        assert!(has_utf8_bom(b"\xEF\xBB\xBF"));
+        assert!(!has_utf8_bom(b"\xEF\xBB"));
+    }
+
+    #[test]
+    fn trimming_and_final_newline_work_alone_and_together() {
+        assert_eq!(trim_trailing_whitespace("a \t\r\nb  \nc "), "a\r\nb\nc");
+        assert_eq!(set_final_newline("a\r\nb", true), "a\r\nb\r\n");
+        assert_eq!(set_final_newline("a\nb\n\n\n", true), "a\nb\n");
+        assert_eq!(set_final_newline("a\nb\n", false), "a\nb");
+        assert_eq!(set_final_newline("", true), "");
+
+        let options = ConvertOptions {
+            with_bom: false,
+            trim_trailing_whitespace: true,
+            ensure_final_newline: Some(true),
+            ..ConvertOptions::default()
+        };
+        let output = convert_with_options(b"a  \nb\t\n\n", "UTF-8", &options).unwrap();
+        assert_eq!(output.data, b"a\nb\n");
+        assert_ne!(options.output_key(), ConvertOptions::default().output_key());
+    }
+
+    #[test]
+    fn bytes_api_honors_the_binary_hint() {
+        let candidates = AppConfig::default().candidate_encodings;
+        let weights = scorer::ScoringWeights::default();
+        let options = ConvertOptions::default();
+        let text = shift_jis("やまだ,とうきょう\n");
+
+        let outcome =
+            detect_and_convert_bytes_with(&text, true, &candidates, &weights, &options).unwrap();
+        assert!(matches!(outcome, DetectionOutcome::Binary));
+        assert_eq!(outcome.encoding_name(), "binary");
+
+        let outcome = detect_and_convert_bytes(b"PK\x03\x04\x14\x00", false).unwrap();
+        assert!(matches!(outcome, DetectionOutcome::Binary));
+    }
+
+    #[test]
+    fn bytes_api_leaves_utf8_alone() {
+        let outcome = detect_and_convert_bytes("名前,住所\n".as_bytes(), true).unwrap();
+        assert!(matches!(
+            outcome,
+            DetectionOutcome::AlreadyUtf8 { has_bom: false }
+        ));
+        assert_eq!(outcome.confidence(), 1.0);
+
+        let outcome = detect_and_convert_bytes(b"\xEF\xBB\xBFa,b\n", false).unwrap();
+        assert_eq!(outcome.encoding_name(), "UTF-8 (BOM)");
+    }
+
+    #[test]
+    fn bytes_api_converts_shift_jis() {
+        let outcome = detect_and_convert_bytes(&shift_jis("やまだ,とうきょう\n"), false).unwrap();
+        match outcome {
+            DetectionOutcome::Converted { detection, output } => {
+                assert_eq!(detection.encoding_name, "Shift_JIS");
+                assert_eq!(output.data, "やまだ,とうきょう\n".as_bytes());
+                assert_eq!(output.replacement_count, 0);
             }
+            other => panic!("expected a conversion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn clean_utf8_prefix_short_circuits_detection() {
+        let candidates = AppConfig::default().candidate_encodings;
+        // Broken bytes past the sampled prefix
+        let mut data = "日本語のテキストです。\n"
+            .repeat(UTF8_SAMPLE_LEN / 30 + 1)
+            .into_bytes();
+        assert!(data.len() > UTF8_SAMPLE_LEN);
+        data.extend_from_slice(b"broken: \x82\xA0");
+        assert!(!is_already_utf8(&data));
+
+        let detection =
+            smart_detect_encoding(&data, &candidates, &scorer::ScoringWeights::default());
+        assert_eq!(detection.encoding_name, "UTF-8");
+        assert_eq!(detection.confidence, UTF8_PREFIX_CONFIDENCE);
+
+        // A sequence cut off by the sample boundary doesn't spoil the prefix
+        let mut long = "あ".repeat(UTF8_SAMPLE_LEN / 3).into_bytes();
+        long.extend_from_slice("い".as_bytes());
+        long.truncate(UTF8_SAMPLE_LEN + 1);
+        assert!(detect_utf8_prefix(&long, &candidates).is_some());
+
+        // Too few multibyte sequences, or UTF-8 not a candidate
+        assert!(detect_utf8_prefix(b"plain ascii \x82\xA0", &candidates).is_none());
+        assert!(detect_utf8_prefix(&data, &["Shift_JIS".to_string()]).is_none());
+    }
+
+    #[test]
+    fn detailed_encoding_labels_resolve() {
+        let detailed = supported_encodings_detailed();
+        assert_eq!(
+            detailed.iter().map(|info| info.label).collect::<Vec<_>>(),
+            supported_encodings()
+        );
+        for info in &detailed {
+            assert_eq!(canonical_encoding_name(info.label), Some(info.label));
+            assert!(
+                info.display_name.starts_with(info.label),
+                "{}",
+                info.display_name
+            );
+            assert!(!info.language.is_empty());
+        }
+    }
+
+    /// UTF-8 `text` misread as `encoding` and saved again as UTF-8.
+    fn double_encode(text: &str, encoding: &'static Encoding) -> String {
+        encoding
+            .decode_without_bom_handling(text.as_bytes())
+            .0
+            .into_owned()
+    }
+
+    #[test]
+    fn double_encoding_is_recovered() {
+        let latin = double_encode("café été", encoding_rs::WINDOWS_1252);
+        assert_eq!(latin, "cafÃ© Ã©tÃ©");
+        assert_eq!(detect_double_encoding(&latin).as_deref(), Some("café été"));
+
+        let japanese = double_encode("日本語のテキスト", encoding_rs::WINDOWS_1252);
+        assert_eq!(
+            detect_double_encoding(&japanese).as_deref(),
+            Some("日本語のテキスト")
+        );
+
+        let sjis = double_encode("名前と住所", encoding_rs::SHIFT_JIS);
+        assert_eq!(sjis, "蜷榊燕縺ｨ菴乗園");
+        assert_eq!(detect_double_encoding(&sjis).as_deref(), Some("名前と住所"));
+    }
+
+    #[test]
+    fn clean_utf8_is_not_flagged_as_double_encoded() {
+        for text in [
+            "hello",
+            "café",
+            "日本語のテキスト",
+            "Straße — “quoted”",
+            "한국어",
+            "Ñandú",
+        ] {
+            assert_eq!(detect_double_encoding(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn encode_with_replacement_round_trips_shift_jis() {
+        let text = "名前,住所\r\n山田太郎,東京都千代田区\r\nｶﾅ\r\n";
+
+        let output = encode_with_replacement(text, "Shift_JIS").unwrap();
+
+        assert_eq!(output.unmappable_count, 0);
+        let (decoded, _, had_errors) = encoding_rs::SHIFT_JIS.decode(&output.data);
+        assert!(!had_errors);
+        assert_eq!(decoded, text);
+    }
+
+    #[test]
+    fn encode_with_replacement_counts_unmappable_characters() {
+        let output = encode_with_replacement("山田😀太郎😀€", "Shift_JIS").unwrap();
+
+        assert_eq!(output.unmappable_count, 3);
+        let (decoded, _, _) = encoding_rs::SHIFT_JIS.decode(&output.data);
+        assert_eq!(decoded, "山田?太郎??");
+
+        let output = encode_with_replacement("あ😀い", "ISO-2022-JP").unwrap();
+        assert_eq!(output.unmappable_count, 1);
+        let (decoded, _, _) = encoding_rs::ISO_2022_JP.decode(&output.data);
+        assert_eq!(decoded, "あ?い");
+    }
+
+    /// Shift_JIS text a few chunks long, with CRLF lines, trailing spaces and half-width
+    /// kana so that multibyte characters straddle the chunk boundaries.
+    fn large_shift_jis() -> Vec<u8> {
+        let mut text = String::new();
+        let mut i = 0;
+        while text.len() < CONVERT_CHUNK_SIZE * 3 {
+            text.push_str(&format!("{},山田太郎,東京都千代田区,ｶﾅ  \r\n", i));
+            i += 1;
         }
+        text.push_str("最後の行\n\n\n");
+        encoding_rs::SHIFT_JIS.encode(&text).0.into_owned()
     }
 
-    false
+    #[test]
+    fn convert_chunked_matches_whole_buffer_conversion() {
+        let data = large_shift_jis();
+        let variants = [
+            ConvertOptions::default(),
+            ConvertOptions {
+                with_bom: false,
+                line_ending: LineEnding::Lf,
+                trim_trailing_whitespace: true,
+                ensure_final_newline: Some(true),
+                ..ConvertOptions::default()
+            },
+            ConvertOptions {
+                line_ending: LineEnding::Crlf,
+                ensure_final_newline: Some(false),
+                ..ConvertOptions::default()
+            },
+        ];
+        for options in variants {
+            let whole = convert_with_options(&data, "Shift_JIS", &options).unwrap();
+            let mut chunked = Vec::new();
+            let mut progress = Vec::new();
+            let output = convert_chunked(&data[..], &mut chunked, "Shift_JIS", &options, |done| {
+                progress.push(done)
+            })
+            .unwrap();
+
+            assert!(
+                chunked == whole.data,
+                "chunked output differs for {:?}",
+                options
+            );
+            assert_eq!(output.replacement_count, 0);
+            assert!(!output.lossy);
+            assert!(progress.len() >= 3);
+            assert_eq!(progress.last(), Some(&(data.len() as u64)));
+        }
+    }
 }
@@ -1,33 +1,336 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::config::AppConfig;
+use crate::encoder::{self, ConvertOptions};
+
+/// Placeholder in `launch_args` replaced with the file path.
+pub const FILE_PLACEHOLDER: &str = "{file}";
+
+/// Opens files in applications. The commands launch through this so the convert
+/// flow can run without spawning processes, e.g. with a recording fake in tests.
+pub trait Launcher {
+    /// Open `file` with `app` ("system_default" or empty = the OS default handler).
+    fn launch(&self, app: &str, file: &str) -> Result<(), String>;
+}
+
+/// The real launcher: `launch_app` with the configured `launch_args` and retries.
+#[derive(Debug, Clone, Default)]
+pub struct SystemLauncher {
+    pub launch_args: Vec<String>,
+    /// Extra attempts after a transient launch failure.
+    pub retries: u32,
+    pub retry_delay: Duration,
+}
+
+impl SystemLauncher {
+    pub fn new(cfg: &AppConfig) -> Self {
+        Self {
+            launch_args: cfg.launch_args.clone(),
+            retries: cfg.launch_retries,
+            retry_delay: Duration::from_millis(cfg.launch_retry_delay_ms),
+        }
+    }
+}
+
+impl Launcher for SystemLauncher {
+    fn launch(&self, app: &str, file: &str) -> Result<(), String> {
+        launch_app(app, &self.launch_args, file, self.retries, self.retry_delay)
+    }
+}
 
 /// Launch the specified application with the given file path.
-/// If app_path is "system_default" or empty, open with the OS default handler.
-pub fn launch_app(app_path: &str, file_path: &str) -> Result<(), String> {
+/// If app_path is "system_default" or empty, open with the OS default handler
+/// (`launch_args` don't apply there).
+/// Transient failures (e.g. the file briefly locked by antivirus) are retried up to
+/// `retries` times, `retry_delay` apart.
+pub fn launch_app(
+    app_path: &str,
+    launch_args: &[String],
+    file_path: &str,
+    retries: u32,
+    retry_delay: Duration,
+) -> Result<(), String> {
     if app_path.is_empty() || app_path == "system_default" {
-        open::that(file_path).map_err(|e| format!("Failed to open file with system default: {}", e))
+        with_retries(retries, retry_delay, || open::that(file_path))
+            .map_err(|e| format!("Failed to open file with system default: {}", e))
     } else {
-        // Verify the app exists
+        // Verify the app exists (a missing app won't appear by retrying)
         if !Path::new(app_path).exists() {
             return Err(format!("Application not found: {}", app_path));
         }
 
-        Command::new(app_path)
-            .arg(file_path)
-            .spawn()
-            .map_err(|e| format!("Failed to launch {}: {}", app_path, e))?;
+        let args = build_launch_args(launch_args, file_path)?;
+        with_retries(retries, retry_delay, || {
+            Command::new(app_path).args(&args).spawn()
+        })
+        .map_err(|e| format!("Failed to launch {}: {}", app_path, e))?;
 
         Ok(())
     }
 }
 
-/// Create a temporary file with the given data and return its path.
-/// The file preserves the original extension (csv, tsv, txt, etc.).
-pub fn create_temp_file(original_name: &str, data: &[u8]) -> Result<String, String> {
-    let temp_dir = std::env::temp_dir().join("encodingman");
-    std::fs::create_dir_all(&temp_dir)
-        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+/// Run `op`, retrying it up to `retries` more times (sleeping `delay` in between)
+/// while it fails with a transient error. Other errors are returned at once.
+pub fn with_retries<T>(
+    retries: u32,
+    delay: Duration,
+    mut op: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    let mut attempt = 0;
+    loop {
+        match op() {
+            Err(e) if attempt < retries && is_transient_launch_error(&e) => {
+                attempt += 1;
+                log::warn!("launch failed ({}), retry {} of {}", e, attempt, retries);
+                thread::sleep(delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Errors worth retrying: not found (an editor or temp file that isn't visible yet
+/// while another process, e.g. antivirus, still holds it), interrupted, and would
+/// block. Anything else, permission errors included, won't go away by waiting.
+fn is_transient_launch_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::NotFound | io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock
+    )
+}
+
+/// Check a `launch_args` template: at most one `{file}` placeholder.
+/// Returns the number of placeholders found.
+pub fn validate_launch_args(template: &[String]) -> Result<usize, String> {
+    let placeholders: usize = template
+        .iter()
+        .map(|arg| arg.matches(FILE_PLACEHOLDER).count())
+        .sum();
+    if placeholders > 1 {
+        return Err(format!(
+            "launch_args may contain at most one {} placeholder, found {}",
+            FILE_PLACEHOLDER, placeholders
+        ));
+    }
+    Ok(placeholders)
+}
+
+/// Build the app's argument list from a `launch_args` template.
+/// `{file}` is replaced with the path; without a placeholder the path is appended.
+/// More than one placeholder is rejected.
+pub fn build_launch_args(template: &[String], file_path: &str) -> Result<Vec<String>, String> {
+    let placeholders = validate_launch_args(template)?;
+
+    let mut args: Vec<String> = template
+        .iter()
+        .map(|arg| arg.replace(FILE_PLACEHOLDER, file_path))
+        .collect();
+    if placeholders == 0 {
+        args.push(file_path.to_string());
+    }
+    Ok(args)
+}
+
+/// Pick the app for a file: the `app_by_extension` entry matching the original
+/// file's extension, else `default_app`, else the system default handler.
+pub fn app_for_file<'a>(cfg: &'a AppConfig, original_path: &str) -> &'a str {
+    let by_extension = Path::new(original_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(|ext| app_for_extension(&cfg.app_by_extension, ext))
+        .filter(|app| !app.trim().is_empty());
+
+    match by_extension {
+        Some(app) => app,
+        None if !cfg.default_app.trim().is_empty() => &cfg.default_app,
+        None => "system_default",
+    }
+}
+
+/// The `app_by_extension` entry for `ext`. Keys may have a leading dot and any case.
+/// An exact key ("csv", then ".csv") wins; otherwise the first matching key in sorted
+/// order does, so the pick never depends on `HashMap` iteration order.
+fn app_for_extension<'a>(apps: &'a HashMap<String, String>, ext: &str) -> Option<&'a str> {
+    apps.get(ext)
+        .or_else(|| apps.get(&format!(".{}", ext)))
+        .or_else(|| {
+            apps.iter()
+                .filter(|(key, _)| key.trim_start_matches('.').eq_ignore_ascii_case(ext))
+                .min_by_key(|(key, _)| key.as_str())
+                .map(|(_, app)| app)
+        })
+        .map(String::as_str)
+}
+
+/// Open `target_path` with the app chosen for `original_path` (see `app_for_file`).
+/// The original path matters because converted temp files may be renamed.
+pub fn launch_for_file(
+    launcher: &dyn Launcher,
+    cfg: &AppConfig,
+    original_path: &str,
+    target_path: &str,
+) -> Result<(), String> {
+    launcher.launch(app_for_file(cfg, original_path), target_path)
+}
+
+/// Default temp directory: %TEMP%/encodingman.
+pub fn default_temp_dir() -> PathBuf {
+    std::env::temp_dir().join("encodingman")
+}
+
+/// The temp directory to use: the configured one, or the default.
+pub fn temp_dir_path(configured: Option<&str>) -> PathBuf {
+    match configured {
+        Some(dir) if !dir.trim().is_empty() => PathBuf::from(dir),
+        _ => default_temp_dir(),
+    }
+}
+
+/// Resolve the temp directory to use (the configured one, or the default),
+/// creating it if needed and checking that it is writable.
+pub fn resolve_temp_dir(configured: Option<&str>) -> Result<PathBuf, String> {
+    let temp_dir = temp_dir_path(configured);
+
+    std::fs::create_dir_all(&temp_dir).map_err(|e| {
+        format!("Failed to create temp directory {}: {}", temp_dir.display(), e)
+    })?;
+
+    // Probe with a throwaway file; read-only or locked-down directories fail here
+    let probe = temp_dir.join(".encodingman_write_test");
+    std::fs::write(&probe, b"")
+        .map_err(|e| format!("Temp directory is not writable {}: {}", temp_dir.display(), e))?;
+    let _ = std::fs::remove_file(&probe);
+
+    Ok(temp_dir)
+}
+
+/// Create a temporary file in `temp_dir` with the given data and return its path.
+/// The name comes from `temp_file_name`, so it keeps the original extension (csv,
+/// tsv, txt, etc.) and same-named files from different folders don't collide.
+/// With `modified`, the temp file's mtime is set to it (best-effort) so tools that
+/// sort by date see the original file's timestamp.
+/// With `reuse_existing`, an existing temp file at least as new as `modified` (the
+/// source file's mtime) is left alone and its path returned, keeping any edits in it.
+/// `source_encoding` and `options` are what `data` was converted from and with; they
+/// are part of the name, so a copy decoded another way or written under other settings
+/// is never reused.
+pub fn create_temp_file(
+    temp_dir: &Path,
+    original_path: &Path,
+    source_encoding: &str,
+    options: &ConvertOptions,
+    data: &[u8],
+    modified: Option<SystemTime>,
+    reuse_existing: bool,
+) -> Result<String, String> {
+    let file_name = temp_file_name(original_path, source_encoding, options);
+    create_named_temp_file(temp_dir, &file_name, data, modified, reuse_existing)
+}
+
+/// `create_temp_file` with an explicit file name (e.g. from `tagged_temp_file_name`).
+pub fn create_named_temp_file(
+    temp_dir: &Path,
+    file_name: &str,
+    data: &[u8],
+    modified: Option<SystemTime>,
+    reuse_existing: bool,
+) -> Result<String, String> {
+    let temp_path = temp_dir.join(file_name);
+
+    if reuse_existing && is_temp_up_to_date(&temp_path, modified) {
+        return temp_path
+            .to_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| "Failed to convert temp path to string".to_string());
+    }
+
+    std::fs::write(&temp_path, data)
+        .map_err(|e| format!("Failed to write temp file: {}", e))?;
+
+    if let Some(mtime) = modified {
+        // A wrong timestamp is not worth failing the conversion over
+        let _ = set_modified_time(&temp_path, mtime);
+    }
+
+    temp_path
+        .to_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| "Failed to convert temp path to string".to_string())
+}
+
+/// `create_temp_file` for output too large to build in memory: `write` streams it
+/// into the temp file. Returns the path and `write`'s result, which is None when an
+/// up-to-date temp file was reused. A failed write leaves no partial file behind.
+pub fn create_temp_file_streamed<T>(
+    temp_dir: &Path,
+    original_path: &Path,
+    source_encoding: &str,
+    options: &ConvertOptions,
+    modified: Option<SystemTime>,
+    reuse_existing: bool,
+    write: impl FnOnce(&mut dyn io::Write) -> Result<T, String>,
+) -> Result<(String, Option<T>), String> {
+    let temp_path = temp_dir.join(temp_file_name(original_path, source_encoding, options));
+    let temp_path_str = temp_path
+        .to_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| "Failed to convert temp path to string".to_string())?;
+
+    if reuse_existing && is_temp_up_to_date(&temp_path, modified) {
+        return Ok((temp_path_str, None));
+    }
+
+    let file = std::fs::File::create(&temp_path)
+        .map_err(|e| format!("Failed to write temp file: {}", e))?;
+    let mut writer = io::BufWriter::new(file);
+    let result = match write(&mut writer) {
+        Ok(result) => result,
+        Err(e) => {
+            drop(writer);
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e);
+        }
+    };
+    // Flush before setting the mtime, or the final write would bump it again
+    writer
+        .into_inner()
+        .map_err(|e| format!("Failed to write temp file: {}", e.error()))?;
+
+    if let Some(mtime) = modified {
+        // A wrong timestamp is not worth failing the conversion over
+        let _ = set_modified_time(&temp_path, mtime);
+    }
+    Ok((temp_path_str, Some(result)))
+}
+
+/// Whether `temp_path` exists and was modified no earlier than the source file.
+/// Fresh temp files carry the source mtime, so "equal" counts as up to date.
+fn is_temp_up_to_date(temp_path: &Path, source_modified: Option<SystemTime>) -> bool {
+    match (modified_time(temp_path), source_modified) {
+        (Some(temp), Some(source)) => temp >= source,
+        _ => false,
+    }
+}
+
+/// The last-modified time of `path`, if the platform reports one.
+pub fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn set_modified_time(path: &Path, mtime: SystemTime) -> std::io::Result<()> {
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(path)?
+        .set_modified(mtime)
+}
 
+/// File name for a converted copy: `report.csv` → `report_utf8.csv`.
+pub fn converted_file_name(original_name: &str) -> String {
     let orig_path = Path::new(original_name);
     let stem = orig_path
         .file_stem()
@@ -37,15 +340,169 @@ pub fn create_temp_file(original_name: &str, data: &[u8]) -> Result<String, Stri
         .extension()
         .and_then(|s| s.to_str())
         .unwrap_or("csv");
-    let temp_path = temp_dir.join(format!("{}_utf8.{}", stem, ext));
+    format!("{}_utf8.{}", stem, ext)
+}
 
-    std::fs::write(&temp_path, data)
-        .map_err(|e| format!("Failed to write temp file: {}", e))?;
+/// Temp file name for a converted copy: `converted_file_name` plus a short hash of
+/// the source's absolute path, `source_encoding` and `options.output_key()`, e.g.
+/// `/a/report.csv` → `report_utf8_1b2c3d4e.csv`. The same source converted the same
+/// way always maps to the same name, so reuse and cleanup still work.
+pub fn temp_file_name(
+    original_path: &Path,
+    source_encoding: &str,
+    options: &ConvertOptions,
+) -> String {
+    let variant = format!("from={} {}", source_encoding, options.output_key());
+    hashed_temp_file_name(original_path, "utf8", &variant)
+}
 
-    temp_path
+/// `temp_file_name` with `tag` in place of "utf8", for copies written in another
+/// encoding: `/a/report.csv` as Shift_JIS → `report_shiftjis_1b2c3d4e.csv`.
+pub fn tagged_temp_file_name(original_path: &Path, tag: &str) -> String {
+    hashed_temp_file_name(original_path, tag, "")
+}
+
+/// `{stem}_{tag}_{hash}.{ext}`, hashing the absolute path plus `variant`.
+fn hashed_temp_file_name(original_path: &Path, tag: &str, variant: &str) -> String {
+    let absolute =
+        std::fs::canonicalize(original_path).unwrap_or_else(|_| original_path.to_path_buf());
+    let hash = name_hash(&absolute, variant);
+
+    let stem = original_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("converted");
+    let ext = original_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("csv");
+    format!("{}_{}_{:08x}.{}", stem, tag, hash, ext)
+}
+
+/// Tag for `encoding_name` in temp file names: its lowercase letters and digits
+/// ("Shift_JIS" → "shiftjis").
+pub fn encoding_tag(encoding_name: &str) -> String {
+    encoding_name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// 32-bit FNV-1a of the path, a NUL, then `variant`. Hand-rolled rather than
+/// `DefaultHasher` because the value must stay stable across builds for temp files
+/// to be reused.
+fn name_hash(path: &Path, variant: &str) -> u32 {
+    let path = path.to_string_lossy();
+    path.bytes()
+        .chain(std::iter::once(0))
+        .chain(variant.bytes())
+        .fold(0x811c_9dc5, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        })
+}
+
+/// Whether a file name looks like one produced by `converted_file_name`,
+/// `temp_file_name` or `tagged_temp_file_name` (`*_utf8.*`, `*_utf8_<8 hex>.*`, or
+/// `*_<encoding tag>_<8 hex>.*`).
+pub fn is_converted_file_name(name: &str) -> bool {
+    let Some(stem) = Path::new(name).file_stem().and_then(|s| s.to_str()) else {
+        return false;
+    };
+    if stem.ends_with("_utf8") {
+        return true;
+    }
+    match stem.rsplit_once('_') {
+        Some((rest, hash)) => {
+            (rest.ends_with("_utf8") || has_encoding_tag(rest))
+                && hash.len() == 8
+                && hash.bytes().all(|b| b.is_ascii_hexdigit())
+        }
+        None => false,
+    }
+}
+
+/// Whether `stem` ends with `_<tag>` for one of the supported encodings.
+fn has_encoding_tag(stem: &str) -> bool {
+    stem.rsplit_once('_').is_some_and(|(_, tag)| {
+        encoder::supported_encodings()
+            .iter()
+            .any(|name| encoding_tag(name) == tag)
+    })
+}
+
+/// Map a file under `source_root` to the same relative location under `output_dir`,
+/// renamed with `converted_file_name`. Same-named files in different subfolders
+/// therefore land in distinct output paths.
+pub fn mirrored_output_path(
+    source_root: &Path,
+    file: &Path,
+    output_dir: &Path,
+) -> Result<PathBuf, String> {
+    let relative = file
+        .strip_prefix(source_root)
+        .map_err(|_| format!("{} is not inside {}", file.display(), source_root.display()))?;
+    let file_name = relative
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("Invalid file name: {}", file.display()))?;
+
+    let mut target = output_dir.join(relative);
+    target.set_file_name(converted_file_name(file_name));
+    Ok(target)
+}
+
+/// Write converted data to `target`, creating parent directories as needed.
+pub fn write_output_file(target: &Path, data: &[u8]) -> Result<String, String> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create output directory: {}", e))?;
+    }
+
+    std::fs::write(target, data).map_err(|e| format!("Failed to write output file: {}", e))?;
+
+    target
         .to_str()
         .map(|s| s.to_string())
-        .ok_or_else(|| "Failed to convert temp path to string".to_string())
+        .ok_or_else(|| "Failed to convert output path to string".to_string())
+}
+
+/// Path of the backup written before an in-place overwrite: `<name>.bak` next to the file.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// Whether `path` is inside a Git working tree, i.e. it or a parent folder has `.git`
+/// (a directory, or a file for worktrees and submodules).
+pub fn is_in_git_repo(path: &Path) -> bool {
+    let absolute = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    absolute.ancestors().any(|dir| dir.join(".git").exists())
+}
+
+/// Replace the contents of `path` with `data`, first copying the original to
+/// `backup_path(path)` when `backup` is set. Returns the overwritten path.
+/// Files under version control are only overwritten with a backup (safe mode).
+pub fn overwrite_in_place(path: &Path, data: &[u8], backup: bool) -> Result<String, String> {
+    if !backup && is_in_git_repo(path) {
+        return Err(format!(
+            "Refusing to overwrite {} inside a Git repository without a backup; \
+             enable backup_before_overwrite to convert it in place",
+            path.display()
+        ));
+    }
+    if backup {
+        std::fs::copy(path, backup_path(path))
+            .map_err(|e| format!("Failed to create backup: {}", e))?;
+    }
+
+    std::fs::write(path, data).map_err(|e| format!("Failed to overwrite file: {}", e))?;
+
+    path
+        .to_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| "Failed to convert file path to string".to_string())
 }
 
 /// Delete a temporary file.
@@ -56,3 +513,391 @@ pub fn cleanup_temp_file(path: &str) -> Result<(), String> {
     }
     Ok(())
 }
+
+/// Delete every converted file (`*_utf8.*`) in `temp_dir` and return how many were removed.
+/// With `older_than`, only files created longer ago than that are removed.
+/// Creation time is used because temp files carry the original file's mtime.
+pub fn cleanup_all_temp_files(temp_dir: &Path, older_than: Option<Duration>) -> Result<usize, String> {
+    if !temp_dir.is_dir() {
+        return Ok(0);
+    }
+
+    let entries = std::fs::read_dir(temp_dir)
+        .map_err(|e| format!("Failed to read temp directory: {}", e))?;
+
+    let mut removed = 0usize;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_converted = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(is_converted_file_name);
+        if !is_converted || !path.is_file() {
+            continue;
+        }
+
+        if let Some(min_age) = older_than {
+            let age = entry
+                .metadata()
+                .and_then(|m| m.created().or_else(|_| m.modified()))
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+            // Unknown age, or fresh enough → leave it alone
+            if age.is_none_or(|age| age < min_age) {
+                continue;
+            }
+        }
+
+        // Files still locked by an open editor just stay until next time
+        if std::fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("encodingman_launcher_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn mirrored_paths_keep_subfolders_apart() {
+        let source_root = Path::new("/project");
+        let output_dir = Path::new("/out");
+
+        let a = mirrored_output_path(source_root, Path::new("/project/a/data.csv"), output_dir)
+            .unwrap();
+        let b = mirrored_output_path(source_root, Path::new("/project/b/data.csv"), output_dir)
+            .unwrap();
+        assert_eq!(a, Path::new("/out/a/data_utf8.csv"));
+        assert_eq!(b, Path::new("/out/b/data_utf8.csv"));
+
+        assert!(
+            mirrored_output_path(source_root, Path::new("/other/data.csv"), output_dir).is_err()
+        );
+    }
+
+    #[test]
+    fn unwritable_temp_dir_is_rejected() {
+        let dir = test_dir("unwritable_temp_dir");
+        // A regular file where a directory should be; fails even when running as root
+        let blocker = dir.join("not_a_dir");
+        std::fs::write(&blocker, b"").unwrap();
+        let configured = blocker.join("temp");
+
+        let err = resolve_temp_dir(Some(configured.to_str().unwrap())).unwrap_err();
+        assert!(err.contains("Failed to create temp directory"));
+        assert!(err.contains(&configured.display().to_string()));
+
+        let writable = dir.join("temp");
+        assert_eq!(
+            resolve_temp_dir(Some(writable.to_str().unwrap())).unwrap(),
+            writable
+        );
+    }
+
+    #[test]
+    fn cleanup_all_removes_only_files_past_the_cutoff() {
+        let dir = test_dir("cleanup_all");
+        let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        for name in ["a_utf8.csv", "b_utf8.csv", "notes.csv"] {
+            std::fs::write(dir.join(name), b"x").unwrap();
+        }
+        // Carried over from an old original; the copy itself is brand new
+        set_modified_time(&dir.join("a_utf8.csv"), hour_ago).unwrap();
+
+        let has_creation_time = std::fs::metadata(dir.join("a_utf8.csv"))
+            .and_then(|m| m.created())
+            .is_ok();
+        if has_creation_time {
+            let removed = cleanup_all_temp_files(&dir, Some(Duration::from_secs(60))).unwrap();
+            assert_eq!(removed, 0);
+        }
+
+        std::thread::sleep(Duration::from_millis(50));
+        let removed = cleanup_all_temp_files(&dir, Some(Duration::from_millis(10))).unwrap();
+        assert_eq!(removed, 2);
+        assert!(dir.join("notes.csv").exists());
+        assert_eq!(
+            cleanup_all_temp_files(&dir.join("missing"), None).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn temp_file_carries_the_original_mtime() {
+        let dir = test_dir("carry_mtime");
+        let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        let options = ConvertOptions::default();
+        let temp = create_temp_file(
+            &dir,
+            Path::new("report.csv"),
+            "Shift_JIS",
+            &options,
+            b"x",
+            Some(hour_ago),
+            false,
+        )
+        .unwrap();
+        assert_eq!(modified_time(Path::new(&temp)), Some(hour_ago));
+
+        let fresh = create_temp_file(
+            &dir,
+            Path::new("fresh.csv"),
+            "Shift_JIS",
+            &options,
+            b"x",
+            None,
+            false,
+        )
+        .unwrap();
+        assert!(modified_time(Path::new(&fresh)).unwrap() > hour_ago);
+    }
+
+    #[test]
+    fn app_by_extension_lookup_is_deterministic() {
+        // Each map gets fresh hash keys, so a lookup relying on iteration order would vary
+        let apps = |entries: &[(&str, &str)]| AppConfig {
+            app_by_extension: entries
+                .iter()
+                .map(|(ext, app)| (ext.to_string(), app.to_string()))
+                .collect(),
+            default_app: "fallback_editor".to_string(),
+            ..AppConfig::default()
+        };
+        let spellings = [(".csv", "dotted"), ("csv", "exact"), ("CSV", "upper")];
+
+        for _ in 0..20 {
+            assert_eq!(app_for_file(&apps(&spellings), "a/report.csv"), "exact");
+            // No exact key: the first spelling in sorted order
+            assert_eq!(app_for_file(&apps(&spellings), "a/report.Csv"), "dotted");
+            let cfg = apps(&[("Csv", "mixed"), ("CSV", "upper")]);
+            assert_eq!(app_for_file(&cfg, "report.csv"), "upper");
+        }
+        // Unmapped extensions use default_app, then the system handler
+        assert_eq!(
+            app_for_file(&apps(&spellings), "report.txt"),
+            "fallback_editor"
+        );
+        let no_default = AppConfig {
+            default_app: String::new(),
+            ..apps(&spellings)
+        };
+        assert_eq!(app_for_file(&no_default, "report.txt"), "system_default");
+        assert_eq!(app_for_file(&no_default, "report.csv"), "exact");
+    }
+
+    #[test]
+    fn launch_args_place_the_file_path() {
+        let args = |template: &[&str]| {
+            let template: Vec<String> = template.iter().map(|s| s.to_string()).collect();
+            build_launch_args(&template, "C:\\tmp\\a b.csv")
+        };
+
+        assert_eq!(args(&[]).unwrap(), vec!["C:\\tmp\\a b.csv"]);
+        assert_eq!(
+            args(&["--readonly", "{file}", "--new-window"]).unwrap(),
+            vec!["--readonly", "C:\\tmp\\a b.csv", "--new-window"]
+        );
+        assert_eq!(
+            args(&["--open={file}"]).unwrap(),
+            vec!["--open=C:\\tmp\\a b.csv"]
+        );
+        assert_eq!(args(&["-n"]).unwrap(), vec!["-n", "C:\\tmp\\a b.csv"]);
+        assert!(args(&["{file}", "{file}"]).is_err());
+        assert!(args(&["{file}{file}"]).is_err());
+    }
+
+    #[test]
+    fn validate_launch_args_counts_placeholders() {
+        let template = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(validate_launch_args(&template(&[])), Ok(0));
+        assert_eq!(validate_launch_args(&template(&["-n", "{file}"])), Ok(1));
+        let err = validate_launch_args(&template(&["{file}", "--log={file}"])).unwrap_err();
+        assert!(err.contains("found 2"));
+    }
+
+    #[test]
+    fn overwrite_in_place_writes_backup_first() {
+        let dir = test_dir("in_place");
+        let file = dir.join("notes.txt");
+        std::fs::write(&file, b"\x82\xA0").unwrap();
+
+        let written = overwrite_in_place(&file, "あ".as_bytes(), true).unwrap();
+        assert_eq!(Path::new(&written), file);
+        assert_eq!(std::fs::read(&file).unwrap(), "あ".as_bytes());
+        assert_eq!(std::fs::read(backup_path(&file)).unwrap(), b"\x82\xA0");
+
+        let other = dir.join("other.txt");
+        std::fs::write(&other, b"old").unwrap();
+        overwrite_in_place(&other, b"new", false).unwrap();
+        assert_eq!(std::fs::read(&other).unwrap(), b"new");
+        assert!(!backup_path(&other).exists());
+    }
+
+    #[test]
+    fn up_to_date_temp_file_is_not_rewritten() {
+        let dir = test_dir("reuse");
+        let source = dir.join("source.csv");
+        std::fs::write(&source, b"source").unwrap();
+        let modified = modified_time(&source);
+        let options = AppConfig::default().convert_options();
+
+        let first = create_temp_file(
+            &dir,
+            &source,
+            "Shift_JIS",
+            &options,
+            b"first",
+            modified,
+            true,
+        )
+        .unwrap();
+        let second = create_temp_file(
+            &dir,
+            &source,
+            "Shift_JIS",
+            &options,
+            b"second",
+            modified,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(std::fs::read(&second).unwrap(), b"first");
+    }
+
+    #[test]
+    fn temp_file_from_other_options_is_not_reused() {
+        let dir = test_dir("reuse_options");
+        let source = dir.join("source.csv");
+        std::fs::write(&source, b"source").unwrap();
+        let modified = modified_time(&source);
+        let lf = AppConfig::default().convert_options();
+        let crlf = ConvertOptions {
+            line_ending: crate::encoder::LineEnding::Crlf,
+            ..lf.clone()
+        };
+
+        let first =
+            create_temp_file(&dir, &source, "Shift_JIS", &lf, b"a\n", modified, true).unwrap();
+        let second =
+            create_temp_file(&dir, &source, "Shift_JIS", &crlf, b"a\r\n", modified, true).unwrap();
+
+        assert_ne!(first, second);
+        assert!(is_converted_file_name(&second));
+        assert_eq!(std::fs::read(&second).unwrap(), b"a\r\n");
+    }
+
+    #[test]
+    fn same_named_files_get_distinct_temp_files() {
+        let dir = test_dir("same_named_temp");
+        let temp_dir = dir.join("temp");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let options = ConvertOptions::default();
+
+        let mut paths = Vec::new();
+        for sub in ["a", "b"] {
+            let source = dir.join(sub).join("data.csv");
+            std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+            std::fs::write(&source, sub).unwrap();
+            let temp = create_temp_file(
+                &temp_dir,
+                &source,
+                "Shift_JIS",
+                &options,
+                sub.as_bytes(),
+                None,
+                false,
+            )
+            .unwrap();
+            paths.push(temp);
+        }
+
+        assert_ne!(paths[0], paths[1]);
+        assert_eq!(std::fs::read(&paths[0]).unwrap(), b"a");
+        assert_eq!(std::fs::read(&paths[1]).unwrap(), b"b");
+        for path in &paths {
+            let name = Path::new(path).file_name().unwrap().to_str().unwrap();
+            assert!(name.starts_with("data_utf8_") && name.ends_with(".csv"));
+            assert!(is_converted_file_name(name));
+        }
+        assert!(!is_converted_file_name("data_utf8_notahash.csv"));
+        assert!(!is_converted_file_name("data.csv"));
+    }
+
+    #[test]
+    fn transient_launch_errors_are_retried() {
+        let mut calls = 0;
+        let result = with_retries(3, Duration::ZERO, || {
+            calls += 1;
+            if calls <= 2 {
+                Err(io::Error::from(io::ErrorKind::NotFound))
+            } else {
+                Ok(calls)
+            }
+        });
+
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn retries_give_up_after_budget() {
+        let mut calls = 0;
+        let result: io::Result<()> = with_retries(2, Duration::ZERO, || {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::Interrupted))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn permission_denied_is_not_retried() {
+        let mut calls = 0;
+        let result: io::Result<()> = with_retries(3, Duration::ZERO, || {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        });
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn overwrite_in_git_repo_requires_backup() {
+        let dir = test_dir("git_repo");
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        let file = dir.join("src").join("data.csv");
+        std::fs::write(&file, b"original").unwrap();
+
+        assert!(overwrite_in_place(&file, b"converted", false).is_err());
+        assert_eq!(std::fs::read(&file).unwrap(), b"original");
+
+        overwrite_in_place(&file, b"converted", true).unwrap();
+        assert_eq!(std::fs::read(&file).unwrap(), b"converted");
+        assert_eq!(std::fs::read(backup_path(&file)).unwrap(), b"original");
+    }
+
+    #[test]
+    fn overwrite_outside_git_repo_is_allowed() {
+        let dir = test_dir("no_git_repo");
+        assert!(!is_in_git_repo(&dir));
+        let file = dir.join("data.csv");
+        std::fs::write(&file, b"original").unwrap();
+
+        overwrite_in_place(&file, b"converted", false).unwrap();
+        assert_eq!(std::fs::read(&file).unwrap(), b"converted");
+        assert!(!backup_path(&file).exists());
+    }
+}
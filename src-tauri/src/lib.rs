@@ -1,12 +1,29 @@
+mod archive;
+mod batch;
+mod cache;
+mod cli;
+mod clipboard;
 mod config;
+mod csv;
 mod encoder;
 mod launcher;
+mod logging;
+mod scan;
 mod scorer;
+mod watch;
 
+use batch::{BatchCancel, BatchResult, Destination};
+use cache::{CacheLookup, DetectionCache};
 use config::AppConfig;
+use encoder::{DetectionOutcome, DetectionResult};
+use launcher::{Launcher, SystemLauncher};
+use scorer::EncodingScore;
+use watch::WatchState;
 use serde::Serialize;
-use std::path::Path;
-use tauri::Manager;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ConvertResult {
@@ -19,29 +36,84 @@ pub struct ConvertResult {
     pub converted_preview: Vec<String>,
     pub original_path: String,
     pub file_name: String,
+    pub replacement_count: usize,
+    pub lossy: bool,
+    /// Whether the conversion re-encodes to the original bytes; None when not checked.
+    pub round_trip_ok: Option<bool>,
+    /// Line-ending style of the original file ("lf", "crlf", "cr", "mixed", or "none").
+    pub line_ending: String,
+    /// Likely field delimiter (",", ";", "\\t" for tab, or "|") for .csv/.tsv files;
+    /// None for other files.
+    pub detected_delimiter: Option<String>,
+    /// Why the file was (or wasn't) converted, in plain Japanese for the UI.
+    pub explanation: String,
+    /// `original_preview` and `converted_preview` paired line by line, padded with
+    /// None where one side is shorter. Empty when nothing was converted.
+    pub preview_diff: Vec<(Option<String>, Option<String>)>,
+    /// Parts of the file seem to be in different encodings, so some of it may be garbled.
+    pub mixed_encoding_warning: bool,
+    /// Why the converted file couldn't be opened (e.g. `default_app` was uninstalled).
+    /// The conversion itself still succeeded; `temp_file_path` can be opened manually.
+    pub launch_error: Option<String>,
+    /// Preview of the text recovered from double-encoded UTF-8 (mojibake saved as
+    /// UTF-8). Empty unless the file looked double-encoded; see `recover_double_encoding`.
+    pub double_encoding_preview: Vec<String>,
 }
 
+/// Result of `analyze_file`.
 #[derive(Debug, Clone, Serialize)]
-pub struct BatchFileResult {
-    pub file_path: String,
-    pub file_name: String,
-    pub status: String, // "converted", "already_utf8", "binary", "error"
-    pub detected_encoding: Option<String>,
-    pub error_message: Option<String>,
+pub struct AnalysisReport {
+    /// Encoding the convert flow picks for the file.
+    pub detected_encoding: String,
+    pub confidence: f32,
+    /// Every candidate's score, best first.
+    pub scores: Vec<EncodingScore>,
 }
 
+/// Progress payload of the "convert-progress" event, sent while a large file is
+/// converted in chunks.
 #[derive(Debug, Clone, Serialize)]
-pub struct BatchResult {
-    pub results: Vec<BatchFileResult>,
-    pub total: usize,
-    pub converted: usize,
-    pub already_utf8: usize,
-    pub binary: usize,
-    pub errors: usize,
+pub struct ConvertProgress {
+    pub file_path: String,
+    pub bytes_processed: u64,
+    pub total_bytes: u64,
 }
 
 #[tauri::command]
-fn detect_and_convert(file_path: String) -> Result<ConvertResult, String> {
+fn detect_and_convert(
+    app: AppHandle,
+    cache: State<'_, DetectionCache>,
+    file_path: String,
+) -> Result<ConvertResult, String> {
+    logging::logged("detect_and_convert", || {
+        let cfg = config::load_config();
+        let on_progress = |bytes_processed, total_bytes| {
+            let progress = ConvertProgress {
+                file_path: file_path.clone(),
+                bytes_processed,
+                total_bytes,
+            };
+            let _ = app.emit("convert-progress", progress);
+        };
+        detect_and_convert_with(
+            file_path.clone(),
+            &cfg,
+            &cache,
+            &SystemLauncher::new(&cfg),
+            &on_progress,
+        )
+    })
+}
+
+/// Body of `detect_and_convert`, opening files through `app_launcher`. Large files
+/// report `(bytes_processed, total_bytes)` to `on_progress` while they convert.
+fn detect_and_convert_with(
+    file_path: String,
+    cfg: &AppConfig,
+    cache: &DetectionCache,
+    app_launcher: &dyn Launcher,
+    on_progress: &dyn Fn(u64, u64),
+) -> Result<ConvertResult, String> {
     let path = Path::new(&file_path);
     if !path.exists() {
         return Err(format!("File not found: {}", file_path));
@@ -53,11 +125,10 @@ fn detect_and_convert(file_path: String) -> Result<ConvertResult, String> {
         .unwrap_or("unknown")
         .to_string();
 
-    let cfg = config::load_config();
 
-    // Binary files → open directly without conversion
-    if encoder::is_binary_file(path) {
-        launcher::launch_app(&cfg.default_app, &file_path)?;
+    // Binary files → open directly without conversion (unless an encoding is forced)
+    if cfg.encoding_override(path).is_none() && encoder::is_binary_file(path) {
+        let launch_error = try_launch(app_launcher, cfg, &file_path, &file_path);
 
         return Ok(ConvertResult {
             auto_converted: true,
@@ -69,238 +140,941 @@ fn detect_and_convert(file_path: String) -> Result<ConvertResult, String> {
             converted_preview: vec![],
             original_path: file_path,
             file_name,
+            replacement_count: 0,
+            lossy: false,
+            round_trip_ok: None,
+            line_ending: encoder::LineEndingKind::None.as_str().to_string(),
+            detected_delimiter: None,
+            explanation: "バイナリファイルのため変換せずに開きました".to_string(),
+            preview_diff: vec![],
+            mixed_encoding_warning: false,
+            launch_error,
+            double_encoding_preview: vec![],
         });
     }
 
+    // Large text files → stream through the converter instead of reading them whole
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if cfg.converts_chunked(size) && !cfg.write_in_place() {
+        let large = convert_large_file(&file_path, size, cfg, cache, app_launcher, on_progress)?;
+        if let Some(result) = large {
+            return Ok(result);
+        }
+    }
+
     // Text files → read, smart detect encoding, always auto-convert
+    let lookup = cache.lookup(path);
     let data = encoder::read_file_bytes(path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
 
-    // Already UTF-8 → open directly without conversion
-    if encoder::is_already_utf8(&data) {
-        launcher::launch_app(&cfg.default_app, &file_path)?;
+    // Smart detect encoding
+    let (detection, converted) = match detect_and_convert_data(path, &data, cfg, cache, lookup)? {
+        DetectionOutcome::Converted { detection, output } => (detection, output),
+        // Already UTF-8 (binary was handled above) → open directly without conversion
+        outcome => {
+            let launch_error = try_launch(app_launcher, cfg, &file_path, &file_path);
+            let text = encoder::decode_for_display(&data, "UTF-8");
+            let detected_delimiter = delimiter_label(path, &text);
+
+            let double_encoding_preview = encoder::detect_double_encoding(&text)
+                .map(|recovered| {
+                    encoder::get_preview_lines(&recovered, cfg.preview_lines, cfg.preview_max_width)
+                })
+                .unwrap_or_default();
+            let explanation = if double_encoding_preview.is_empty() {
+                format!("{} のため変換は不要です", outcome.encoding_name())
+            } else {
+                "UTF-8 ですが、二重にエンコードされた文字化けの可能性があります".to_string()
+            };
+
+            return Ok(ConvertResult {
+                auto_converted: true,
+                is_binary: false,
+                detected_encoding: outcome.encoding_name().to_string(),
+                confidence: outcome.confidence(),
+                temp_file_path: None,
+                original_preview: vec![],
+                converted_preview: vec![],
+                original_path: file_path,
+                file_name,
+                replacement_count: 0,
+                lossy: false,
+                round_trip_ok: None,
+                line_ending: line_ending_label(&text),
+                detected_delimiter,
+                explanation,
+                preview_diff: vec![],
+                mixed_encoding_warning: false,
+                launch_error,
+                double_encoding_preview,
+            });
+        }
+    };
+    log::info!(
+        "detected {}: {} (confidence {:.2}, {} replacements)",
+        file_path,
+        detection.encoding_name,
+        detection.confidence,
+        converted.replacement_count
+    );
+    let text = encoder::decode_for_display(&data, &detection.encoding_name);
+    let line_ending = line_ending_label(&text);
+    let detected_delimiter = delimiter_label(path, &text);
+    let explanation = explain_detection(
+        &detection.encoding_name,
+        detection.confidence,
+        converted.replacement_count,
+    );
+
+    let original_preview = generate_preview(
+        &data,
+        &detection.encoding_name,
+        cfg.preview_lines,
+        cfg.preview_max_width,
+    );
+    let converted_preview = generate_preview(
+        &converted.data,
+        "UTF-8",
+        cfg.preview_lines,
+        cfg.preview_max_width,
+    );
+    let preview_diff = zip_previews(&original_preview, &converted_preview);
+    let mixed_encoding_warning =
+        scorer::detect_mixed_encoding(&data, &cfg.candidate_encodings, &cfg.scoring_weights);
 
+    // Low confidence → don't write or launch anything; let the user pick an encoding
+    if detection.confidence < cfg.confidence_threshold {
         return Ok(ConvertResult {
-            auto_converted: true,
+            auto_converted: false,
             is_binary: false,
-            detected_encoding: "UTF-8".to_string(),
-            confidence: 1.0,
+            detected_encoding: detection.encoding_name,
+            confidence: detection.confidence,
             temp_file_path: None,
-            original_preview: vec![],
-            converted_preview: vec![],
+            original_preview,
+            converted_preview,
             original_path: file_path,
             file_name,
+            replacement_count: converted.replacement_count,
+            lossy: converted.lossy,
+            round_trip_ok: converted.round_trip_ok,
+            line_ending,
+            detected_delimiter,
+            explanation,
+            preview_diff,
+            mixed_encoding_warning,
+            launch_error: None,
+            double_encoding_preview: vec![],
         });
     }
 
-    // Smart detect encoding
-    let detection = encoder::smart_detect_encoding(&data);
-
-    let original_preview = generate_preview(&data, &detection.encoding_name, cfg.preview_lines);
-
-    // Always auto-convert (Smart Auto-Fix)
-    let converted_data = encoder::convert_to_utf8_bom(&data, &detection.encoding_name)?;
-    let converted_preview = generate_preview(&converted_data, "UTF-8", cfg.preview_lines);
-
-    let temp_path = launcher::create_temp_file(&file_name, &converted_data)?;
-    launcher::launch_app(&cfg.default_app, &temp_path)?;
+    // Confident enough → auto-convert (Smart Auto-Fix)
+    // A failed launch doesn't undo the conversion; it is reported in launch_error
+    let (temp_path, launch_error) = if cfg.write_in_place() {
+        launcher::overwrite_in_place(path, &converted.data, cfg.backup_before_overwrite)?;
+        let launch_error = try_launch(app_launcher, cfg, &file_path, &file_path);
+        (None, launch_error)
+    } else {
+        let temp_dir = launcher::resolve_temp_dir(cfg.temp_dir.as_deref())?;
+        let temp_path = launcher::create_temp_file(
+            &temp_dir,
+            path,
+            &detection.encoding_name,
+            &cfg.convert_options(),
+            &converted.data,
+            launcher::modified_time(path),
+            cfg.reuse_temp,
+        )?;
+        let launch_error = try_launch(app_launcher, cfg, &file_path, &temp_path);
+        (Some(temp_path), launch_error)
+    };
 
     Ok(ConvertResult {
         auto_converted: true,
         is_binary: false,
         detected_encoding: detection.encoding_name,
         confidence: detection.confidence,
-        temp_file_path: Some(temp_path),
+        temp_file_path: temp_path,
         original_preview,
         converted_preview,
         original_path: file_path,
         file_name,
+        replacement_count: converted.replacement_count,
+        lossy: converted.lossy,
+        round_trip_ok: converted.round_trip_ok,
+        line_ending,
+        detected_delimiter,
+        explanation,
+        preview_diff,
+        mixed_encoding_warning,
+        launch_error,
+        double_encoding_preview: vec![],
     })
 }
 
-#[tauri::command]
-fn convert_with_encoding(file_path: String, encoding: String) -> Result<String, String> {
-    let path = Path::new(&file_path);
+/// Head of a large file that detection and the previews look at.
+const LARGE_FILE_SAMPLE_LEN: usize = 1024 * 1024;
+
+/// `detect_and_convert_with` for files above `chunked_threshold_mb`: detection samples
+/// the head of the file and the conversion streams into the temp file. Returns None
+/// when the detected encoding can't be streamed, leaving the file to the normal path.
+fn convert_large_file(
+    file_path: &str,
+    total_bytes: u64,
+    cfg: &AppConfig,
+    cache: &DetectionCache,
+    app_launcher: &dyn Launcher,
+    on_progress: &dyn Fn(u64, u64),
+) -> Result<Option<ConvertResult>, String> {
+    let path = Path::new(file_path);
     let file_name = path
         .file_name()
         .and_then(|n| n.to_str())
-        .unwrap_or("unknown.csv");
+        .unwrap_or("unknown")
+        .to_string();
+    let lookup = cache.lookup(path);
+    let sample = encoder::read_file_prefix(path, LARGE_FILE_SAMPLE_LEN)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let options = cfg.convert_options();
+    let open_original =
+        || std::fs::File::open(path).map_err(|e| format!("Failed to read file: {}", e));
+
+    let overridden = cfg.encoding_override(path);
+    // Checked before scoring, which can't tell plain ASCII from the other candidates
+    let sample_is_utf8 = overridden.is_none() && encoder::is_utf8_prefix(&sample);
+    let detection = match overridden {
+        Some(encoding) => DetectionResult {
+            encoding_name: encoding.to_string(),
+            confidence: 1.0,
+        },
+        None if sample_is_utf8 => DetectionResult {
+            encoding_name: "UTF-8".to_string(),
+            confidence: 1.0,
+        },
+        None => cache.detect(path, lookup, &sample, cfg),
+    };
+    if !encoder::can_convert_chunked(&detection.encoding_name) {
+        return Ok(None);
+    }
+    let text = encoder::decode_for_display(&sample, &detection.encoding_name);
+    let line_ending = line_ending_label(&text);
+    let detected_delimiter = delimiter_label(path, &text);
 
+    // UTF-8 all the way through → open directly without conversion, like small files
+    if sample_is_utf8 && !options.strips_bom(&sample) {
+        let check = encoder::convert_chunked(
+            open_original()?,
+            std::io::sink(),
+            "UTF-8",
+            &encoder::ConvertOptions::default(),
+            |done| on_progress(done, total_bytes),
+        )?;
+        if !check.lossy {
+            let launch_error = try_launch(app_launcher, cfg, file_path, file_path);
+            let encoding_name = encoder::utf8_display_name(&sample);
+            return Ok(Some(ConvertResult {
+                auto_converted: true,
+                is_binary: false,
+                detected_encoding: encoding_name.to_string(),
+                confidence: 1.0,
+                temp_file_path: None,
+                original_preview: vec![],
+                converted_preview: vec![],
+                original_path: file_path.to_string(),
+                file_name,
+                replacement_count: 0,
+                lossy: false,
+                round_trip_ok: None,
+                line_ending,
+                detected_delimiter,
+                explanation: format!("{} のため変換は不要です", encoding_name),
+                preview_diff: vec![],
+                mixed_encoding_warning: false,
+                launch_error,
+                double_encoding_preview: vec![],
+            }));
+        }
+    }
+
+    let sample_output = encoder::convert_with_options(&sample, &detection.encoding_name, &options)?;
+    let original_preview = generate_preview(
+        &sample,
+        &detection.encoding_name,
+        cfg.preview_lines,
+        cfg.preview_max_width,
+    );
+    let converted_preview = generate_preview(
+        &sample_output.data,
+        "UTF-8",
+        cfg.preview_lines,
+        cfg.preview_max_width,
+    );
+    let preview_diff = zip_previews(&original_preview, &converted_preview);
+    let mixed_encoding_warning =
+        scorer::detect_mixed_encoding(&sample, &cfg.candidate_encodings, &cfg.scoring_weights);
+
+    // Low confidence → don't write or launch anything; let the user pick an encoding
+    if detection.confidence < cfg.confidence_threshold {
+        return Ok(Some(ConvertResult {
+            auto_converted: false,
+            is_binary: false,
+            explanation: explain_detection(
+                &detection.encoding_name,
+                detection.confidence,
+                sample_output.replacement_count,
+            ),
+            detected_encoding: detection.encoding_name,
+            confidence: detection.confidence,
+            temp_file_path: None,
+            original_preview,
+            converted_preview,
+            original_path: file_path.to_string(),
+            file_name,
+            replacement_count: sample_output.replacement_count,
+            lossy: sample_output.lossy,
+            round_trip_ok: None,
+            line_ending,
+            detected_delimiter,
+            preview_diff,
+            mixed_encoding_warning,
+            launch_error: None,
+            double_encoding_preview: vec![],
+        }));
+    }
+
+    let temp_dir = launcher::resolve_temp_dir(cfg.temp_dir.as_deref())?;
+    let (temp_path, output) = launcher::create_temp_file_streamed(
+        &temp_dir,
+        path,
+        &detection.encoding_name,
+        &options,
+        launcher::modified_time(path),
+        cfg.reuse_temp,
+        |writer| {
+            encoder::convert_chunked(
+                open_original()?,
+                writer,
+                &detection.encoding_name,
+                &options,
+                |done| on_progress(done, total_bytes),
+            )
+        },
+    )?;
+    // A reused temp file wasn't converted again, so nothing was lost this time
+    let (replacement_count, lossy) = output.map_or((0, false), |output| {
+        (output.replacement_count, output.lossy)
+    });
+    log::info!(
+        "detected {}: {} (confidence {:.2}, {} replacements, chunked)",
+        file_path,
+        detection.encoding_name,
+        detection.confidence,
+        replacement_count
+    );
+    let launch_error = try_launch(app_launcher, cfg, file_path, &temp_path);
+
+    Ok(Some(ConvertResult {
+        auto_converted: true,
+        is_binary: false,
+        explanation: explain_detection(
+            &detection.encoding_name,
+            detection.confidence,
+            replacement_count,
+        ),
+        detected_encoding: detection.encoding_name,
+        confidence: detection.confidence,
+        temp_file_path: Some(temp_path),
+        original_preview,
+        converted_preview,
+        original_path: file_path.to_string(),
+        file_name,
+        replacement_count,
+        lossy,
+        round_trip_ok: None,
+        line_ending,
+        detected_delimiter,
+        preview_diff,
+        mixed_encoding_warning,
+        launch_error,
+        double_encoding_preview: vec![],
+    }))
+}
+
+/// `launcher::launch_for_file`, logging a failure and returning it instead of failing
+/// the whole conversion.
+fn try_launch(
+    app_launcher: &dyn Launcher,
+    cfg: &AppConfig,
+    original: &str,
+    target: &str,
+) -> Option<String> {
+    let result = launcher::launch_for_file(app_launcher, cfg, original, target);
+    if let Err(e) = &result {
+        log::warn!("failed to open {}: {}", target, e);
+    }
+    result.err()
+}
+
+/// A temp copy written by one of the conversion commands, and whether it opened.
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenedFile {
+    pub temp_path: String,
+    /// Why the copy couldn't be opened; see `ConvertResult::launch_error`.
+    pub launch_error: Option<String>,
+}
+
+impl OpenedFile {
+    /// Open `temp_path` with the app chosen for `original` (see `try_launch`).
+    fn launch(
+        app_launcher: &dyn Launcher,
+        cfg: &AppConfig,
+        original: &str,
+        temp_path: String,
+    ) -> Self {
+        let launch_error = try_launch(app_launcher, cfg, original, &temp_path);
+        Self {
+            temp_path,
+            launch_error,
+        }
+    }
+}
+
+#[tauri::command]
+fn convert_with_encoding(file_path: String, encoding: String) -> Result<OpenedFile, String> {
+    logging::logged("convert_with_encoding", || {
+        let cfg = config::load_config();
+        let temp_path = convert_to_temp_with_encoding(&file_path, &encoding, &cfg)?;
+        let app_launcher = SystemLauncher::new(&cfg);
+        Ok(OpenedFile::launch(
+            &app_launcher,
+            &cfg,
+            &file_path,
+            temp_path,
+        ))
+    })
+}
+
+/// Redo a conversion from the original file with a user-chosen encoding, overwriting
+/// the same temp file an earlier (wrong) guess wrote, so the editor reloads it.
+#[tauri::command]
+fn reconvert(original_path: String, encoding: String) -> Result<OpenedFile, String> {
+    logging::logged("reconvert", || {
+        if !Path::new(&original_path).exists() {
+            return Err(format!("File not found: {}", original_path));
+        }
+
+        let cfg = config::load_config();
+        let temp_path = convert_to_temp_with_encoding(&original_path, &encoding, &cfg)?;
+        let app_launcher = SystemLauncher::new(&cfg);
+        Ok(OpenedFile::launch(
+            &app_launcher,
+            &cfg,
+            &original_path,
+            temp_path,
+        ))
+    })
+}
+
+/// Undo double encoding (see `encoder::detect_double_encoding`) into the temp copy of
+/// `file_path` and open it. Fails when the file doesn't look double-encoded.
+#[tauri::command]
+fn recover_double_encoding(file_path: String) -> Result<OpenedFile, String> {
+    logging::logged("recover_double_encoding", || {
+        let cfg = config::load_config();
+        let path = Path::new(&file_path);
+        let data = encoder::read_file_bytes(path)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        let text = encoder::decode_for_display(&data, "UTF-8");
+        let recovered = encoder::detect_double_encoding(&text)
+            .ok_or_else(|| format!("No double encoding found in {}", file_path))?;
+
+        let options = cfg.convert_options();
+        let converted = encoder::convert_with_options(recovered.as_bytes(), "UTF-8", &options)?;
+        let temp_dir = launcher::resolve_temp_dir(cfg.temp_dir.as_deref())?;
+        let temp_path = launcher::create_temp_file(
+            &temp_dir,
+            path,
+            "UTF-8",
+            &options,
+            &converted.data,
+            launcher::modified_time(path),
+            // A garbled copy written earlier must be replaced
+            false,
+        )?;
+        let app_launcher = SystemLauncher::new(&cfg);
+        Ok(OpenedFile::launch(
+            &app_launcher,
+            &cfg,
+            &file_path,
+            temp_path,
+        ))
+    })
+}
+
+/// Convert `file_path` into `target_encoding` (e.g. Shift_JIS for systems that
+/// require it) and write it to a temp copy tagged with that encoding. Returns the temp
+/// path. Characters the target can't represent become `?` and are logged.
+#[tauri::command]
+fn convert_to(
+    cache: State<'_, DetectionCache>,
+    file_path: String,
+    target_encoding: String,
+) -> Result<String, String> {
+    logging::logged("convert_to", || {
+        let cfg = config::load_config();
+        convert_to_with(&file_path, &target_encoding, &cfg, &cache).map(|(temp_path, _)| temp_path)
+    })
+}
+
+/// `convert_to` with an explicit config. Also returns how many characters couldn't be
+/// represented in the target encoding.
+fn convert_to_with(
+    file_path: &str,
+    target_encoding: &str,
+    cfg: &AppConfig,
+    cache: &DetectionCache,
+) -> Result<(String, usize), String> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Err(format!("File not found: {}", file_path));
+    }
+    let target = encoder::canonical_encoding_name(target_encoding)
+        .ok_or_else(|| format!("Unknown encoding: {}", target_encoding))?;
+    if cfg.encoding_override(path).is_none() && encoder::is_binary_file(path) {
+        return Err(format!("Binary file, nothing to convert: {}", file_path));
+    }
+
+    let lookup = cache.lookup(path);
     let data = encoder::read_file_bytes(path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
+    let source = match cfg.encoding_override(path) {
+        Some(encoding) => encoding.to_string(),
+        None if encoder::is_already_utf8(&data) => "UTF-8".to_string(),
+        None => cache.detect(path, lookup, &data, cfg).encoding_name,
+    };
+    let text = encoder::decode_for_display(&data, &source);
+    let output = encoder::encode_with_replacement(&text, target)?;
+    if output.unmappable_count > 0 {
+        log::warn!(
+            "{}: {} characters can't be represented in {}",
+            file_path,
+            output.unmappable_count,
+            target
+        );
+    }
 
-    let converted_data = encoder::convert_to_utf8_bom(&data, &encoding)?;
-    let temp_path = launcher::create_temp_file(file_name, &converted_data)?;
+    let temp_dir = launcher::resolve_temp_dir(cfg.temp_dir.as_deref())?;
+    let file_name = launcher::tagged_temp_file_name(path, &launcher::encoding_tag(target));
+    let temp_path = launcher::create_named_temp_file(
+        &temp_dir,
+        &file_name,
+        &output.data,
+        launcher::modified_time(path),
+        false,
+    )?;
+    Ok((temp_path, output.unmappable_count))
+}
 
-    let cfg = config::load_config();
-    launcher::launch_app(&cfg.default_app, &temp_path)?;
+/// Convert `file_path` from `encoding` into its temp copy, always rewriting it.
+/// Returns the temp path; nothing is launched.
+fn convert_to_temp_with_encoding(
+    file_path: &str,
+    encoding: &str,
+    cfg: &AppConfig,
+) -> Result<String, String> {
+    let path = Path::new(file_path);
+    let data = encoder::read_file_bytes(path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
 
-    Ok(temp_path)
+    let options = cfg.convert_options();
+    let converted = encoder::convert_with_options(&data, encoding, &options)?;
+    // Named after the encoding the auto flow picks, so this replaces the copy a wrong
+    // guess wrote instead of adding one next to it
+    let guessed = match cfg.encoding_override(path) {
+        Some(encoding) => encoding.to_string(),
+        None => {
+            encoder::smart_detect_encoding(&data, &cfg.candidate_encodings, &cfg.scoring_weights)
+                .encoding_name
+        }
+    };
+    let temp_dir = launcher::resolve_temp_dir(cfg.temp_dir.as_deref())?;
+    launcher::create_temp_file(
+        &temp_dir,
+        path,
+        &guessed,
+        &options,
+        &converted.data,
+        launcher::modified_time(path),
+        // An explicitly chosen encoding must replace whatever an earlier guess wrote
+        false,
+    )
 }
 
 #[tauri::command]
-fn batch_convert(file_paths: Vec<String>) -> Result<BatchResult, String> {
-    let cfg = config::load_config();
-    let mut results = Vec::new();
-    let mut converted_count = 0usize;
-    let mut utf8_count = 0usize;
-    let mut binary_count = 0usize;
-    let mut error_count = 0usize;
-
-    for file_path in &file_paths {
-        let path = Path::new(file_path);
-        let file_name = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string();
+async fn batch_convert(
+    app: AppHandle,
+    cancel: State<'_, BatchCancel>,
+    cache: State<'_, DetectionCache>,
+    file_paths: Vec<String>,
+    dry_run: Option<bool>,
+) -> Result<BatchResult, String> {
+    logging::logged("batch_convert", || {
+        let cfg = config::load_config();
+        let dry_run = dry_run.unwrap_or(false);
+        let app_launcher = SystemLauncher::new(&cfg);
+        run_batch(
+            &app,
+            &cancel,
+            &cache,
+            &cfg,
+            &app_launcher,
+            &file_paths,
+            dry_run,
+        )
+    })
+}
 
-        if !path.exists() {
-            error_count += 1;
-            results.push(BatchFileResult {
-                file_path: file_path.clone(),
-                file_name,
-                status: "error".to_string(),
-                detected_encoding: None,
-                error_message: Some("File not found".to_string()),
-            });
-            continue;
+/// Scan `folder_path` (with the configured scan filters, narrowed like `scan_folder`)
+/// and batch-convert what it finds in one call, as if the files had been dropped
+/// individually.
+#[tauri::command]
+async fn convert_folder(
+    app: AppHandle,
+    cancel: State<'_, BatchCancel>,
+    cache: State<'_, DetectionCache>,
+    folder_path: String,
+    extensions: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+) -> Result<BatchResult, String> {
+    logging::logged("convert_folder", || {
+        let path = Path::new(&folder_path);
+        if !path.is_dir() {
+            return Err("Not a directory".to_string());
         }
 
-        // Binary passthrough
-        if encoder::is_binary_file(path) {
-            let _ = launcher::launch_app(&cfg.default_app, file_path);
-            binary_count += 1;
-            results.push(BatchFileResult {
-                file_path: file_path.clone(),
-                file_name,
-                status: "binary".to_string(),
-                detected_encoding: None,
-                error_message: None,
-            });
-            continue;
-        }
+        let cfg = config::load_config();
+        let options = folder_scan_options(&cfg, extensions, exclude_globs)?;
+        let file_paths = scan::collect_text_files(path, &options)?;
+        let app_launcher = SystemLauncher::new(&cfg);
+        run_batch(
+            &app,
+            &cancel,
+            &cache,
+            &cfg,
+            &app_launcher,
+            &file_paths,
+            false,
+        )
+    })
+}
 
-        match encoder::read_file_bytes(path) {
-            Ok(data) => {
-                if encoder::is_already_utf8(&data) {
-                    let _ = launcher::launch_app(&cfg.default_app, file_path);
-                    utf8_count += 1;
-                    results.push(BatchFileResult {
-                        file_path: file_path.clone(),
-                        file_name,
-                        status: "already_utf8".to_string(),
-                        detected_encoding: Some("UTF-8".to_string()),
-                        error_message: None,
-                    });
-                } else {
-                    let detection = encoder::smart_detect_encoding(&data);
-                    match encoder::convert_to_utf8_bom(&data, &detection.encoding_name) {
-                        Ok(converted) => {
-                            match launcher::create_temp_file(&file_name, &converted) {
-                                Ok(temp_path) => {
-                                    let _ = launcher::launch_app(&cfg.default_app, &temp_path);
-                                    converted_count += 1;
-                                    results.push(BatchFileResult {
-                                        file_path: file_path.clone(),
-                                        file_name,
-                                        status: "converted".to_string(),
-                                        detected_encoding: Some(detection.encoding_name),
-                                        error_message: None,
-                                    });
-                                }
-                                Err(e) => {
-                                    error_count += 1;
-                                    results.push(BatchFileResult {
-                                        file_path: file_path.clone(),
-                                        file_name,
-                                        status: "error".to_string(),
-                                        detected_encoding: Some(detection.encoding_name),
-                                        error_message: Some(e),
-                                    });
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error_count += 1;
-                            results.push(BatchFileResult {
-                                file_path: file_path.clone(),
-                                file_name,
-                                status: "error".to_string(),
-                                detected_encoding: Some(detection.encoding_name),
-                                error_message: Some(e),
-                            });
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                error_count += 1;
-                results.push(BatchFileResult {
-                    file_path: file_path.clone(),
-                    file_name,
-                    status: "error".to_string(),
-                    detected_encoding: None,
-                    error_message: Some(format!("{}", e)),
-                });
-            }
+/// Shared body of `batch_convert` and `convert_folder`: convert in parallel, then
+/// launch the results serially.
+fn run_batch(
+    app: &AppHandle,
+    cancel: &BatchCancel,
+    cache: &DetectionCache,
+    cfg: &AppConfig,
+    app_launcher: &dyn Launcher,
+    file_paths: &[String],
+    dry_run: bool,
+) -> Result<BatchResult, String> {
+    cancel.0.store(false, Ordering::Relaxed);
+
+    // Overlapping selections (a folder plus a file in it) must not convert a file twice
+    let (unique_paths, duplicates_skipped) = batch::dedupe_paths(file_paths);
+
+    // Dry runs only detect: nothing is written (not even the temp dir) and nothing is launched
+    let destination = if dry_run {
+        Destination::DryRun
+    } else if cfg.write_in_place() {
+        Destination::InPlace {
+            backup: cfg.backup_before_overwrite,
         }
+    } else {
+        Destination::Temp(launcher::resolve_temp_dir(cfg.temp_dir.as_deref())?)
+    };
+
+    // Read/detect/convert/write in parallel, reporting each finished file to the UI.
+    // A failed emit only loses a progress tick, so it never aborts the batch.
+    let on_progress = |progress| {
+        let _ = app.emit("batch-progress", progress);
+    };
+    let items = batch::process_all(
+        &unique_paths,
+        cfg,
+        &destination,
+        cache,
+        &cancel.0,
+        on_progress,
+    );
+    let cancelled = cancel.0.load(Ordering::Relaxed);
+
+    // Launch serially afterwards so the editor isn't hit by a burst of parallel spawns.
+    // A cancelled batch opens nothing.
+    if !cancelled {
+        batch::launch_items(&items, cfg.batch_launch_mode(), |file_path, launch_path| {
+            launcher::launch_for_file(app_launcher, cfg, file_path, launch_path)
+        });
     }
 
-    Ok(BatchResult {
-        total: file_paths.len(),
-        converted: converted_count,
-        already_utf8: utf8_count,
-        binary: binary_count,
-        errors: error_count,
-        results,
+    let results = items.into_iter().map(|item| item.result).collect();
+    let mut result = BatchResult::from_results(file_paths.len(), results);
+    result.cancelled = cancelled;
+    result.duplicates_skipped = duplicates_skipped;
+    Ok(result)
+}
+
+/// Convert `file_paths` and pack the results into a single ZIP at `zip_path`.
+/// Nothing is launched.
+#[tauri::command]
+async fn batch_convert_to_zip(
+    app: AppHandle,
+    cancel: State<'_, BatchCancel>,
+    cache: State<'_, DetectionCache>,
+    file_paths: Vec<String>,
+    zip_path: String,
+) -> Result<BatchResult, String> {
+    logging::logged("batch_convert_to_zip", || {
+        let cfg = config::load_config();
+        cancel.0.store(false, Ordering::Relaxed);
+
+        let on_progress = |progress| {
+            let _ = app.emit("batch-progress", progress);
+        };
+        let mut result = archive::batch_to_zip(
+            &file_paths,
+            &cfg,
+            Path::new(&zip_path),
+            &cache,
+            &cancel.0,
+            on_progress,
+        )?;
+        result.cancelled = cancel.0.load(Ordering::Relaxed);
+        Ok(result)
     })
 }
 
+/// Convert every text file under `folder_path` into `output_dir`, mirroring the
+/// folder structure. The scan is narrowed like `scan_folder`. Nothing is launched.
 #[tauri::command]
-fn scan_folder(folder_path: String) -> Result<Vec<String>, String> {
-    let path = Path::new(&folder_path);
-    if !path.is_dir() {
-        return Err("Not a directory".to_string());
-    }
-
-    let mut files = Vec::new();
-    collect_text_files(path, &mut files)?;
-    Ok(files)
-}
-
-fn collect_text_files(dir: &Path, files: &mut Vec<String>) -> Result<(), String> {
-    let entries = std::fs::read_dir(dir)
-        .map_err(|e| format!("Failed to read directory: {}", e))?;
-
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("{}", e))?;
-        let path = entry.path();
-        if path.is_dir() {
-            collect_text_files(&path, files)?;
-        } else if !encoder::is_binary_file(&path) {
-            // Only include files with known text extensions
-            if has_text_extension(&path) {
-                if let Some(s) = path.to_str() {
-                    files.push(s.to_string());
-                }
-            }
+async fn convert_folder_to(
+    app: AppHandle,
+    cancel: State<'_, BatchCancel>,
+    cache: State<'_, DetectionCache>,
+    folder_path: String,
+    output_dir: String,
+    extensions: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+) -> Result<BatchResult, String> {
+    logging::logged("convert_folder_to", || {
+        let source_root = Path::new(&folder_path);
+        if !source_root.is_dir() {
+            return Err("Not a directory".to_string());
         }
-    }
-    Ok(())
+
+        let cfg = config::load_config();
+        cancel.0.store(false, Ordering::Relaxed);
+
+        let options = folder_scan_options(&cfg, extensions, exclude_globs)?;
+        let file_paths = scan::collect_text_files(source_root, &options)?;
+
+        let destination = Destination::Mirror {
+            source_root: source_root.to_path_buf(),
+            output_dir: PathBuf::from(output_dir),
+        };
+        let on_progress = |progress| {
+            let _ = app.emit("batch-progress", progress);
+        };
+        let items = batch::process_all(
+            &file_paths,
+            &cfg,
+            &destination,
+            &cache,
+            &cancel.0,
+            on_progress,
+        );
+
+        let results = items.into_iter().map(|item| item.result).collect();
+        let mut result = BatchResult::from_results(file_paths.len(), results);
+        result.cancelled = cancel.0.load(Ordering::Relaxed);
+        Ok(result)
+    })
 }
 
-fn has_text_extension(path: &Path) -> bool {
-    const TEXT_EXTENSIONS: &[&str] = &[
-        "csv", "tsv", "txt", "xml", "xsl", "xslt", "json", "htm", "html", "dat", "log",
-        "md", "yml", "yaml", "toml", "ini", "cfg", "conf", "properties",
-        "sql", "sh", "bat", "cmd", "ps1",
-    ];
-    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-        TEXT_EXTENSIONS.contains(&ext.to_lowercase().as_str())
+/// Save the per-file results of a batch to `out_path` as "csv" or "json".
+/// CSV gets a BOM when the target encoding does, so Excel opens it cleanly.
+#[tauri::command]
+fn export_batch_report(
+    result: BatchResult,
+    format: String,
+    out_path: String,
+) -> Result<(), String> {
+    logging::logged("export_batch_report", || {
+        let mut report = batch::batch_report(&result.results, &format)?;
+        if format.trim().eq_ignore_ascii_case("csv") && config::load_config().target_with_bom() {
+            report.insert(0, '\u{FEFF}');
+        }
+        std::fs::write(&out_path, report).map_err(|e| format!("Failed to write report: {}", e))
+    })
+}
+
+#[tauri::command]
+fn cancel_batch(cancel: State<'_, BatchCancel>) {
+    cancel.0.store(true, Ordering::Relaxed);
+}
+
+#[tauri::command]
+fn start_watching(
+    app: AppHandle,
+    watcher: State<'_, WatchState>,
+    folder_path: String,
+) -> Result<(), String> {
+    logging::logged("start_watching", || {
+        let mut current = watcher.0.lock().map_err(|e| format!("Watch state poisoned: {}", e))?;
+        // Only one folder is watched at a time
+        if let Some(previous) = current.take() {
+            previous.stop();
+        }
+
+        let handle = watch::start(Path::new(&folder_path), move |result| {
+            let _ = app.emit("watch-converted", result);
+        })?;
+        *current = Some(handle);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn stop_watching(watcher: State<'_, WatchState>) -> Result<(), String> {
+    logging::logged("stop_watching", || {
+        let mut current = watcher.0.lock().map_err(|e| format!("Watch state poisoned: {}", e))?;
+        if let Some(handle) = current.take() {
+            handle.stop();
+        }
+        Ok(())
+    })
+}
+
+/// `extensions` replaces the built-in text extension list; `exclude_globs` skips
+/// matching paths (relative to `folder_path`). Both None = the default scan.
+#[tauri::command]
+fn scan_folder(
+    folder_path: String,
+    extensions: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+) -> Result<Vec<String>, String> {
+    logging::logged("scan_folder", || {
+        let path = Path::new(&folder_path);
+        if !path.is_dir() {
+            return Err("Not a directory".to_string());
+        }
+
+        let cfg = config::load_config();
+        let options = folder_scan_options(&cfg, extensions, exclude_globs)?;
+        scan::collect_text_files(path, &options)
+    })
+}
+
+/// The configured scan options with the folder commands' optional filters applied.
+fn folder_scan_options(
+    cfg: &AppConfig,
+    extensions: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+) -> Result<scan::ScanOptions, String> {
+    Ok(scan::ScanOptions {
+        extensions,
+        exclude: exclude_globs
+            .map(|patterns| scan::build_exclude_set(&patterns))
+            .transpose()?,
+        ..cfg.scan_options()
+    })
+}
+
+#[tauri::command]
+fn analyze_file(
+    cache: State<'_, DetectionCache>,
+    file_path: String,
+) -> Result<AnalysisReport, String> {
+    logging::logged("analyze_file", || {
+        let path = Path::new(&file_path);
+        if !path.exists() {
+            return Err(format!("File not found: {}", file_path));
+        }
+
+        let cfg = config::load_config();
+        analyze_data(path, &cfg, &cache)
+    })
+}
+
+/// Body of `analyze_file`: the candidate scores plus the encoding the convert flow
+/// would pick, which a shortcut (e.g. the UTF-8 check) can choose over the top score.
+fn analyze_data(
+    path: &Path,
+    cfg: &AppConfig,
+    cache: &DetectionCache,
+) -> Result<AnalysisReport, String> {
+    let lookup = cache.lookup(path);
+    let data = encoder::read_file_bytes(path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let scores = encoder::detection_report(&data, &cfg.candidate_encodings, &cfg.scoring_weights);
+    let detection = detect_text_encoding(path, &data, cfg, cache, lookup);
+    Ok(AnalysisReport {
+        detected_encoding: detection.encoding_name,
+        confidence: detection.confidence,
+        scores,
+    })
+}
+
+#[tauri::command]
+fn preview_file(
+    cache: State<'_, DetectionCache>,
+    file_path: String,
+    encoding: String,
+    lines: usize,
+) -> Result<Vec<String>, String> {
+    logging::logged("preview_file", || {
+        preview_with(&file_path, &encoding, lines, &config::load_config(), &cache)
+    })
+}
+
+/// Body of `preview_file`.
+fn preview_with(
+    file_path: &str,
+    encoding: &str,
+    lines: usize,
+    cfg: &AppConfig,
+    cache: &DetectionCache,
+) -> Result<Vec<String>, String> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Err(format!("File not found: {}", file_path));
+    }
+
+    let lookup = cache.lookup(path);
+    let data = encoder::read_file_bytes(path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    // Empty encoding → detect it the same way the convert flow does
+    let encoding_name = if encoding.trim().is_empty() {
+        detect_text_encoding(path, &data, cfg, cache, lookup).encoding_name
     } else {
-        false
+        encoding.to_string()
+    };
+
+    if encoder::canonical_encoding_name(&encoding_name).is_none() {
+        return Err(format!("Unknown encoding: {}", encoding_name));
     }
+
+    Ok(generate_preview(&data, encoding_name.trim(), lines, cfg.preview_max_width))
+}
+
+/// Fix mojibake in the clipboard text in place and return a short status.
+#[tauri::command]
+fn convert_clipboard() -> Result<String, String> {
+    logging::logged("convert_clipboard", || {
+        clipboard::convert_clipboard(&config::load_config())
+    })
 }
 
 #[tauri::command]
@@ -309,8 +1083,44 @@ fn get_config() -> Result<AppConfig, String> {
 }
 
 #[tauri::command]
-fn update_config(new_config: AppConfig) -> Result<(), String> {
-    config::save_config(&new_config)
+fn take_config_warning() -> Option<String> {
+    config::take_load_error().map(|e| e.to_string())
+}
+
+#[tauri::command]
+fn update_config(cache: State<'_, DetectionCache>, new_config: AppConfig) -> Result<(), String> {
+    logging::logged("update_config", || {
+        config::save_config(&new_config)?;
+        logging::set_level(&new_config.log_level);
+        // Candidates and weights may have changed, so earlier detections no longer apply
+        cache.clear();
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn clear_detection_cache(cache: State<'_, DetectionCache>) {
+    cache.clear();
+}
+
+/// Always convert `path` from `encoding`, skipping detection. An empty `encoding`
+/// removes the override.
+#[tauri::command]
+fn set_encoding_override(path: String, encoding: String) -> Result<(), String> {
+    logging::logged("set_encoding_override", || {
+        let encoding = match encoding.trim() {
+            "" => None,
+            label => Some(
+                encoder::canonical_encoding_name(label)
+                    .ok_or_else(|| format!("Unknown encoding: {}", label))?
+                    .to_string(),
+            ),
+        };
+
+        let mut cfg = config::load_config();
+        cfg.set_encoding_override(Path::new(&path), encoding);
+        config::save_config(&cfg)
+    })
 }
 
 #[tauri::command]
@@ -318,26 +1128,142 @@ fn get_supported_encodings() -> Vec<&'static str> {
     encoder::supported_encodings()
 }
 
+#[tauri::command]
+fn get_supported_encodings_detailed() -> Vec<encoder::EncodingInfo> {
+    encoder::supported_encodings_detailed()
+}
+
 #[tauri::command]
 fn open_converted_file(temp_path: String) -> Result<(), String> {
-    let cfg = config::load_config();
-    launcher::launch_app(&cfg.default_app, &temp_path)
+    logging::logged("open_converted_file", || {
+        let cfg = config::load_config();
+        // Temp files keep the original extension, so the temp path picks the same app
+        launcher::launch_for_file(&SystemLauncher::new(&cfg), &cfg, &temp_path, &temp_path)
+    })
 }
 
 #[tauri::command]
 fn cleanup_temp(temp_path: String) -> Result<(), String> {
-    launcher::cleanup_temp_file(&temp_path)
+    logging::logged("cleanup_temp", || {
+        launcher::cleanup_temp_file(&temp_path)
+    })
+}
+
+#[tauri::command]
+fn cleanup_all_temp(older_than_secs: Option<u64>) -> Result<usize, String> {
+    logging::logged("cleanup_all_temp", || {
+        let cfg = config::load_config();
+        let temp_dir = launcher::temp_dir_path(cfg.temp_dir.as_deref());
+        launcher::cleanup_all_temp_files(&temp_dir, older_than_secs.map(Duration::from_secs))
+    })
+}
+
+/// From this many replaced characters on, the explanation warns that the detected
+/// encoding is probably wrong rather than the file containing a few stray bytes.
+const REPLACEMENT_WARNING_MIN: usize = 5;
+
+/// Human-readable reason for a conversion, shown to the user with ConvertResult.
+fn explain_detection(encoding_name: &str, confidence: f32, replacement_count: usize) -> String {
+    if replacement_count >= REPLACEMENT_WARNING_MIN {
+        format!(
+            "警告: {} 文字が置換されました — 判定したエンコード ({}) が誤っている可能性があります",
+            replacement_count, encoding_name
+        )
+    } else {
+        format!(
+            "{} と判定しました (信頼度 {:.2})。変換できなかった文字は {} 個です",
+            encoding_name, confidence, replacement_count
+        )
+    }
+}
+
+/// Classify the original text's line endings for ConvertResult.
+fn line_ending_label(text: &str) -> String {
+    encoder::detect_line_ending(text).as_str().to_string()
+}
+
+/// Detect the field delimiter for ConvertResult (.csv/.tsv only).
+fn delimiter_label(path: &Path, text: &str) -> Option<String> {
+    csv::is_delimited_file(path).then(|| csv::delimiter_label(csv::detect_delimiter(text)))
+}
+
+/// Pair preview lines for a two-column view; the shorter side is padded with None.
+fn zip_previews(
+    original: &[String],
+    converted: &[String],
+) -> Vec<(Option<String>, Option<String>)> {
+    (0..original.len().max(converted.len()))
+        .map(|i| (original.get(i).cloned(), converted.get(i).cloned()))
+        .collect()
+}
+
+fn generate_preview(
+    data: &[u8],
+    encoding_name: &str,
+    max_lines: usize,
+    max_width: usize,
+) -> Vec<String> {
+    let decoded = encoder::decode_for_display(data, encoding_name);
+    encoder::get_preview_lines(&decoded, max_lines, max_width)
+}
+
+/// The encoding the convert flow would use for text `data` read from `path` after
+/// `lookup`. UTF-8 is recognized before scoring and never cached, so a cache hit
+/// always names an encoding that needs converting.
+fn detect_text_encoding(
+    path: &Path,
+    data: &[u8],
+    cfg: &AppConfig,
+    cache: &DetectionCache,
+    lookup: CacheLookup,
+) -> DetectionResult {
+    if let Some(encoding) = cfg.encoding_override(path) {
+        return DetectionResult {
+            encoding_name: encoding.to_string(),
+            confidence: 1.0,
+        };
+    }
+    if encoder::is_already_utf8(data) {
+        return DetectionResult {
+            encoding_name: encoder::utf8_display_name(data).to_string(),
+            confidence: 1.0,
+        };
+    }
+    cache.detect(path, lookup, data, cfg)
 }
 
-fn generate_preview(data: &[u8], encoding_name: &str, max_lines: usize) -> Vec<String> {
-    let encoding = encoding_rs::Encoding::for_label(encoding_name.as_bytes())
-        .unwrap_or(encoding_rs::UTF_8);
-    let (decoded, _, _) = encoding.decode(data);
-    encoder::get_preview_lines(&decoded, max_lines)
+/// Detect the encoding of `data` (read from `path` after `lookup`) and convert it per
+/// the config, reusing the cached detection while the file is unchanged.
+/// An encoding override for `path` skips detection and is reported at full confidence.
+/// Pure: nothing is written or launched, so the commands and the CLI share it.
+/// Callers screen binary files by path (`is_binary_file`) before reading them.
+fn detect_and_convert_data(
+    path: &Path,
+    data: &[u8],
+    cfg: &AppConfig,
+    cache: &DetectionCache,
+    lookup: CacheLookup,
+) -> Result<DetectionOutcome, String> {
+    if let Some(encoding) = cfg.encoding_override(path) {
+        let output = encoder::convert_with_options(data, encoding, &cfg.convert_options())?;
+        return Ok(DetectionOutcome::Converted {
+            detection: DetectionResult {
+                encoding_name: encoding.to_string(),
+                confidence: 1.0,
+            },
+            output,
+        });
+    }
+
+    encoder::detect_and_convert_bytes_using(data, false, &cfg.convert_options(), |data| {
+        cache.detect(path, lookup, data, cfg)
+    })
 }
 
 /// Process a single file silently (no UI). Used by the setup hook.
-/// Returns Ok(true) if file was handled (binary, UTF-8, or converted).
+/// Returns Ok(true) if file was handled (binary, UTF-8, or converted) and opened.
+/// A file that couldn't be opened gives Ok(false) so the window shows instead; a
+/// converted copy is kept either way.
 fn process_file_silent(file_path: &str) -> Result<bool, String> {
     let path = Path::new(file_path);
     if !path.exists() {
@@ -345,43 +1271,69 @@ fn process_file_silent(file_path: &str) -> Result<bool, String> {
     }
 
     let cfg = config::load_config();
+    let app_launcher = SystemLauncher::new(&cfg);
 
-    // Binary files → open directly
-    if encoder::is_binary_file(path) {
-        launcher::launch_app(&cfg.default_app, file_path)?;
-        return Ok(true);
+    // Binary files → open directly (unless an encoding is forced)
+    if cfg.encoding_override(path).is_none() && encoder::is_binary_file(path) {
+        return Ok(try_launch(&app_launcher, &cfg, file_path, file_path).is_none());
     }
 
+    // One file per run, so there is nothing for a cache to remember
+    let cache = DetectionCache::default();
+    let lookup = cache.lookup(path);
     let data = encoder::read_file_bytes(path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
 
-    // Already UTF-8 → open directly
-    if encoder::is_already_utf8(&data) {
-        launcher::launch_app(&cfg.default_app, file_path)?;
-        return Ok(true);
-    }
-
-    // Needs conversion → smart detect + convert + open
-    let detection = encoder::smart_detect_encoding(&data);
-    let converted_data = encoder::convert_to_utf8_bom(&data, &detection.encoding_name)?;
-
-    let file_name = path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown");
-    let temp_path = launcher::create_temp_file(file_name, &converted_data)?;
-    launcher::launch_app(&cfg.default_app, &temp_path)?;
+    // Already UTF-8 → open directly; otherwise smart detect + convert + open
+    let (detection, converted) = match detect_and_convert_data(path, &data, &cfg, &cache, lookup)? {
+        DetectionOutcome::Converted { detection, output } => (detection, output),
+        _ => return Ok(try_launch(&app_launcher, &cfg, file_path, file_path).is_none()),
+    };
+    log::info!(
+        "detected {}: {} (confidence {:.2}, {} replacements)",
+        file_path,
+        detection.encoding_name,
+        detection.confidence,
+        converted.replacement_count
+    );
 
-    Ok(true)
+    let temp_dir = launcher::resolve_temp_dir(cfg.temp_dir.as_deref())?;
+    let temp_path = launcher::create_temp_file(
+        &temp_dir,
+        path,
+        &detection.encoding_name,
+        &cfg.convert_options(),
+        &converted.data,
+        launcher::modified_time(path),
+        cfg.reuse_temp,
+    )?;
+    Ok(try_launch(&app_launcher, &cfg, file_path, &temp_path).is_none())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // A log that can't be opened is not worth refusing to start over
+    let _ = logging::init(&config::load_config().log_level);
+
+    // Headless CLI modes exit before any window or app launch is involved
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::parse_args(&args) {
+        Some(Ok(command)) => std::process::exit(cli::run(command)),
+        Some(Err(usage)) => {
+            eprintln!("{}", usage);
+            std::process::exit(2);
+        }
+        None => {}
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .manage(BatchCancel::default())
+        .manage(WatchState::default())
+        .manage(DetectionCache::default())
         .setup(|app| {
             // Check CLI args for file path (file association on Windows passes file as arg)
             let args: Vec<String> = std::env::args().collect();
@@ -405,14 +1357,389 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             detect_and_convert,
             convert_with_encoding,
+            reconvert,
+            recover_double_encoding,
+            convert_to,
             batch_convert,
+            batch_convert_to_zip,
+            convert_folder,
+            convert_folder_to,
+            export_batch_report,
+            cancel_batch,
+            start_watching,
+            stop_watching,
             scan_folder,
+            analyze_file,
+            preview_file,
+            convert_clipboard,
             get_config,
+            take_config_warning,
             update_config,
+            clear_detection_cache,
+            set_encoding_override,
             get_supported_encodings,
+            get_supported_encodings_detailed,
             open_converted_file,
             cleanup_temp,
+            cleanup_all_temp,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Records launches instead of opening anything.
+    #[derive(Default)]
+    struct RecordingLauncher(RefCell<Vec<(String, String)>>);
+
+    impl Launcher for RecordingLauncher {
+        fn launch(&self, app: &str, file: &str) -> Result<(), String> {
+            self.0
+                .borrow_mut()
+                .push((app.to_string(), file.to_string()));
+            Ok(())
+        }
+    }
+
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("encodingman_lib_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn test_config(dir: &Path) -> AppConfig {
+        AppConfig {
+            temp_dir: Some(dir.join("temp").to_string_lossy().into_owned()),
+            ..AppConfig::default()
+        }
+    }
+
+    fn no_progress(_: u64, _: u64) {}
+
+    #[test]
+    fn converted_file_is_launched_once() {
+        let dir = test_dir("launch_once");
+        let file = dir.join("sjis.txt");
+        let text = "これはShift_JISで保存されたテキストです。\r\n日本語の文章が続きます。\r\n";
+        std::fs::write(&file, encoding_rs::SHIFT_JIS.encode(text).0).unwrap();
+        let cfg = test_config(&dir);
+        let launcher = RecordingLauncher::default();
+
+        let result = detect_and_convert_with(
+            file.to_string_lossy().into(),
+            &cfg,
+            &DetectionCache::default(),
+            &launcher,
+            &no_progress,
+        )
+        .unwrap();
+
+        assert!(result.auto_converted);
+        let temp_path = result.temp_file_path.unwrap();
+        let launches = launcher.0.borrow();
+        assert_eq!(launches.len(), 1);
+        assert_eq!(launches[0].1, temp_path);
+    }
+
+    #[test]
+    fn low_confidence_detection_is_not_auto_converted() {
+        let file = test_dir("low_confidence").join("short.txt");
+        // High bytes that no candidate decodes into plausible text
+        std::fs::write(&file, [0x85, 0xDF, 0xB5, 0xFB, 0xDF, 0x90]).unwrap();
+
+        let result = detect_and_convert_with(
+            file.to_string_lossy().into_owned(),
+            &AppConfig::default(),
+            &DetectionCache::default(),
+            &RecordingLauncher::default(),
+            &no_progress,
+        )
+        .unwrap();
+        assert!(result.confidence < AppConfig::default().confidence_threshold);
+        assert!(!result.auto_converted);
+        assert_eq!(result.temp_file_path, None);
+        assert!(!result.original_preview.is_empty());
+    }
+
+    #[test]
+    fn preview_follows_the_chosen_encoding() {
+        let dir = test_dir("preview");
+        let path = dir.join("sjis.csv");
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("名前,住所\nやまだ,とうきょう\n");
+        std::fs::write(&path, &sjis[..]).unwrap();
+        let file_path = path.to_string_lossy().to_string();
+        let cfg = AppConfig::default();
+        let cache = DetectionCache::default();
+
+        let as_sjis = preview_with(&file_path, "Shift_JIS", 10, &cfg, &cache).unwrap();
+        assert_eq!(as_sjis, vec!["名前,住所", "やまだ,とうきょう"]);
+        let as_latin = preview_with(&file_path, "windows-1252", 10, &cfg, &cache).unwrap();
+        assert_ne!(as_latin, as_sjis);
+        assert_eq!(
+            preview_with(&file_path, "", 1, &cfg, &cache).unwrap(),
+            vec!["名前,住所"]
+        );
+        assert!(preview_with(&file_path, "no-such-encoding", 10, &cfg, &cache).is_err());
+    }
+
+    #[test]
+    fn reconvert_rewrites_the_same_temp_copy() {
+        let dir = test_dir("reconvert");
+        let path = dir.join("guess.csv");
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("やまだ,とうきょう\n");
+        std::fs::write(&path, &sjis[..]).unwrap();
+        let cfg = AppConfig {
+            temp_dir: Some(dir.join("temp").to_string_lossy().into_owned()),
+            ..AppConfig::default()
+        };
+        let file_path = path.to_string_lossy().to_string();
+
+        let wrong = convert_to_temp_with_encoding(&file_path, "windows-1252", &cfg).unwrap();
+        let wrong_bytes = std::fs::read(&wrong).unwrap();
+        let right = convert_to_temp_with_encoding(&file_path, "Shift_JIS", &cfg).unwrap();
+
+        assert_eq!(wrong, right);
+        let right_bytes = std::fs::read(&right).unwrap();
+        assert_ne!(right_bytes, wrong_bytes);
+        assert!(String::from_utf8(right_bytes).unwrap().contains("やまだ"));
+    }
+
+    #[test]
+    fn folder_conversion_applies_scan_filters() {
+        let dir = test_dir("folder_filters");
+        std::fs::create_dir_all(dir.join("skip")).unwrap();
+        for name in ["keep.csv", "notes.txt", "skip/other.csv"] {
+            std::fs::write(dir.join(name), "a,b\n").unwrap();
+        }
+
+        let options = folder_scan_options(
+            &AppConfig::default(),
+            Some(vec!["csv".to_string()]),
+            Some(vec!["skip/**".to_string()]),
+        )
+        .unwrap();
+        let file_paths = scan::collect_text_files(&dir, &options).unwrap();
+
+        assert_eq!(file_paths, [dir.join("keep.csv").to_string_lossy()]);
+    }
+
+    #[test]
+    fn analyzed_utf8_file_stays_utf8_in_a_dry_run() {
+        let file = test_dir("analyze_utf8").join("notes.txt");
+        std::fs::write(&file, "こんにちは、世界\n").unwrap();
+        let cfg = AppConfig::default();
+        let cache = DetectionCache::default();
+
+        let report = analyze_data(&file, &cfg, &cache).unwrap();
+        assert_eq!(report.detected_encoding, "UTF-8");
+        assert!(report
+            .scores
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+        let preview = preview_with(&file.to_string_lossy(), "", 1, &cfg, &cache).unwrap();
+        assert_eq!(preview, vec!["こんにちは、世界"]);
+
+        let item = batch::process_file(&file.to_string_lossy(), &cfg, &Destination::DryRun, &cache);
+        assert_eq!(item.result.status, "already_utf8");
+    }
+
+    #[test]
+    fn explanation_warns_about_many_replacements() {
+        let fine = explain_detection("Shift_JIS", 0.95, REPLACEMENT_WARNING_MIN - 1);
+        assert!(fine.starts_with("Shift_JIS と判定しました"));
+        let warning = explain_detection("Shift_JIS", 0.95, REPLACEMENT_WARNING_MIN);
+        assert!(warning.starts_with("警告:"));
+        assert!(warning.contains("Shift_JIS"));
+    }
+
+    #[test]
+    fn preview_diff_pads_the_shorter_side() {
+        let original = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let converted = vec!["A".to_string()];
+        assert_eq!(
+            zip_previews(&original, &converted),
+            vec![
+                (Some("a".to_string()), Some("A".to_string())),
+                (Some("b".to_string()), None),
+                (Some("c".to_string()), None),
+            ]
+        );
+        assert_eq!(
+            zip_previews(&[], &converted),
+            vec![(None, Some("A".to_string()))]
+        );
+    }
+
+    #[test]
+    fn override_does_not_reuse_the_copy_decoded_from_the_old_guess() {
+        let dir = test_dir("override_reuse");
+        let path = dir.join("guess.txt");
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("これはShift_JISのテキストです。\n");
+        std::fs::write(&path, &sjis[..]).unwrap();
+        let temp_dir = dir.join("temp");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let mut cfg = AppConfig::default();
+        assert!(cfg.reuse_temp);
+
+        // What detect_and_convert writes for the detected (then overridden) encoding
+        let write_temp = |cfg: &AppConfig| {
+            let cache = DetectionCache::default();
+            let outcome =
+                detect_and_convert_data(&path, &sjis, cfg, &cache, cache.lookup(&path)).unwrap();
+            let DetectionOutcome::Converted { detection, output } = outcome else {
+                panic!("Shift_JIS bytes should be converted");
+            };
+            let temp = launcher::create_temp_file(
+                &temp_dir,
+                &path,
+                &detection.encoding_name,
+                &cfg.convert_options(),
+                &output.data,
+                launcher::modified_time(&path),
+                cfg.reuse_temp,
+            )
+            .unwrap();
+            (detection.encoding_name, std::fs::read(temp).unwrap())
+        };
+
+        let (guessed, guessed_bytes) = write_temp(&cfg);
+        assert_eq!(guessed, "Shift_JIS");
+        cfg.set_encoding_override(&path, Some("EUC-JP".to_string()));
+        let (forced, forced_bytes) = write_temp(&cfg);
+        assert_eq!(forced, "EUC-JP");
+        assert_ne!(forced_bytes, guessed_bytes);
+    }
+
+    #[test]
+    fn missing_default_app_does_not_fail_conversion() {
+        let dir = test_dir("missing_app");
+        let file = dir.join("sjis.txt");
+        let text = "これはShift_JISで保存されたテキストです。\r\n日本語の文章が続きます。\r\n";
+        std::fs::write(&file, encoding_rs::SHIFT_JIS.encode(text).0).unwrap();
+        let cfg = AppConfig {
+            default_app: dir.join("uninstalled-editor.exe").to_string_lossy().into(),
+            ..test_config(&dir)
+        };
+        let app_launcher = SystemLauncher::new(&cfg);
+
+        let result = detect_and_convert_with(
+            file.to_string_lossy().into(),
+            &cfg,
+            &DetectionCache::default(),
+            &app_launcher,
+            &no_progress,
+        )
+        .unwrap();
+
+        assert!(result.auto_converted);
+        let temp_path = result.temp_file_path.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&temp_path).unwrap(),
+            format!("\u{FEFF}{}", text)
+        );
+        assert!(result
+            .launch_error
+            .unwrap()
+            .contains("Application not found"));
+
+        let opened = OpenedFile::launch(&app_launcher, &cfg, &result.original_path, temp_path);
+        assert!(opened.launch_error.is_some());
+    }
+
+    #[test]
+    fn convert_to_shift_jis_and_back() {
+        let dir = test_dir("convert_to");
+        let text = "名前,住所\n山田太郎,東京都千代田区😀\n鈴木花子,大阪府大阪市\n";
+        let file = dir.join("people.csv");
+        std::fs::write(&file, text).unwrap();
+        let cfg = test_config(&dir);
+
+        let (sjis_path, unmappable) = convert_to_with(
+            &file.to_string_lossy(),
+            "shift_jis",
+            &cfg,
+            &DetectionCache::default(),
+        )
+        .unwrap();
+
+        assert_eq!(unmappable, 1);
+        assert!(sjis_path.contains("_shiftjis_"), "{}", sjis_path);
+        let replaced = text.replace('😀', "?");
+        let expected = encoding_rs::SHIFT_JIS.encode(&replaced).0.into_owned();
+        assert_eq!(std::fs::read(&sjis_path).unwrap(), expected);
+
+        let (utf8_path, unmappable) =
+            convert_to_with(&sjis_path, "UTF-8", &cfg, &DetectionCache::default()).unwrap();
+        assert_eq!(unmappable, 0);
+        assert_eq!(std::fs::read_to_string(utf8_path).unwrap(), replaced);
+    }
+
+    #[test]
+    fn large_ascii_file_is_opened_as_utf8() {
+        let dir = test_dir("large_ascii");
+        let file = dir.join("big.log");
+        std::fs::write(
+            &file,
+            "2024-01-01 12:00:00 INFO request handled\n".repeat(40_000),
+        )
+        .unwrap();
+        let cfg = AppConfig {
+            chunked_threshold_mb: 1,
+            ..test_config(&dir)
+        };
+        let launcher = RecordingLauncher::default();
+
+        let result = detect_and_convert_with(
+            file.to_string_lossy().into(),
+            &cfg,
+            &DetectionCache::default(),
+            &launcher,
+            &no_progress,
+        )
+        .unwrap();
+
+        assert!(result.auto_converted);
+        assert_eq!(result.detected_encoding, "UTF-8");
+        assert_eq!(result.temp_file_path, None);
+        assert_eq!(launcher.0.borrow().len(), 1);
+    }
+
+    #[test]
+    fn large_file_chunked_output_matches_whole_file_path() {
+        let dir = test_dir("large_sjis");
+        let mut text = String::new();
+        while text.len() < 1536 * 1024 {
+            text.push_str("名前,住所,電話番号\r\n山田太郎,東京都千代田区,03-1234-5678\r\n");
+        }
+        let data = encoding_rs::SHIFT_JIS.encode(&text).0.into_owned();
+        let file = dir.join("big.csv");
+        std::fs::write(&file, &data).unwrap();
+        let cfg = AppConfig {
+            chunked_threshold_mb: 1,
+            ..test_config(&dir)
+        };
+        let progress = RefCell::new(Vec::new());
+
+        let result = detect_and_convert_with(
+            file.to_string_lossy().into(),
+            &cfg,
+            &DetectionCache::default(),
+            &RecordingLauncher::default(),
+            &|done, total| progress.borrow_mut().push((done, total)),
+        )
+        .unwrap();
+
+        assert_eq!(result.detected_encoding, "Shift_JIS");
+        let written = std::fs::read(result.temp_file_path.unwrap()).unwrap();
+        let whole =
+            encoder::convert_with_options(&data, "Shift_JIS", &cfg.convert_options()).unwrap();
+        assert!(written == whole.data);
+        let total = data.len() as u64;
+        assert_eq!(progress.borrow().last(), Some(&(total, total)));
+    }
+}
@@ -0,0 +1,143 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Once the log reaches this size it is rotated to `encodingman.log.1`,
+/// so at most about twice this much is kept on disk.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+const LOG_FILE_NAME: &str = "encodingman.log";
+
+static LOGGER: OnceLock<FileLogger> = OnceLock::new();
+
+/// Appends log records to a file, rotating it when it grows past `MAX_LOG_BYTES`.
+struct FileLogger {
+    path: PathBuf,
+    /// Serializes writes and rotation across threads (batch workers log too).
+    lock: Mutex<()>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let Ok(_guard) = self.lock.lock() else {
+            return;
+        };
+
+        // Logging must never break a conversion, so write failures are dropped
+        rotate_if_full(&self.path);
+        if let Ok(mut file) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+        {
+            let secs = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let _ = writeln!(file, "{} {:<5} {}", secs, record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn rotate_if_full(path: &Path) {
+    let full = fs::metadata(path)
+        .map(|m| m.len() >= MAX_LOG_BYTES)
+        .unwrap_or(false);
+    if full {
+        let mut rotated = path.as_os_str().to_os_string();
+        rotated.push(".1");
+        let _ = fs::rename(path, rotated);
+    }
+}
+
+/// Log directory: %APPDATA%/encodingman/logs
+pub fn log_dir() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("encodingman");
+    path.push("logs");
+    path
+}
+
+/// Parse a config value ("off", "error", "warn", "info", "debug", "trace").
+/// Unknown values fall back to "info".
+pub fn parse_level(label: &str) -> LevelFilter {
+    LevelFilter::from_str(label.trim()).unwrap_or(LevelFilter::Info)
+}
+
+/// Start logging to `log_dir()` at the configured `log_level`.
+pub fn init(log_level: &str) -> Result<PathBuf, String> {
+    init_at(&log_dir(), log_level)
+}
+
+/// Start logging to `<dir>/encodingman.log`. Only the first call installs the logger;
+/// later calls just change the level. Returns the log file path.
+pub fn init_at(dir: &Path, log_level: &str) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+
+    let logger = LOGGER.get_or_init(|| FileLogger {
+        path: dir.join(LOG_FILE_NAME),
+        lock: Mutex::new(()),
+    });
+    // Fails only if a logger is already installed, which is fine
+    let _ = log::set_logger(logger);
+    set_level(log_level);
+    Ok(logger.path.clone())
+}
+
+/// Change the log level at runtime (e.g. after the config is saved).
+pub fn set_level(log_level: &str) {
+    log::set_max_level(parse_level(log_level));
+}
+
+/// Run a command body and log the error it returns, if any.
+pub fn logged<T>(command: &str, body: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    let result = body();
+    if let Err(e) = &result {
+        log::error!("{}: {}", command, e);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_log_is_rotated() {
+        let dir = std::env::temp_dir().join("encodingman_logging_rotate");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(LOG_FILE_NAME);
+        let rotated = dir.join(format!("{}.1", LOG_FILE_NAME));
+
+        fs::write(&path, vec![b'x'; MAX_LOG_BYTES as usize - 1]).unwrap();
+        rotate_if_full(&path);
+        assert!(path.exists());
+        assert!(!rotated.exists());
+
+        fs::write(&path, vec![b'x'; MAX_LOG_BYTES as usize]).unwrap();
+        rotate_if_full(&path);
+        assert!(!path.exists());
+        assert_eq!(fs::metadata(&rotated).unwrap().len(), MAX_LOG_BYTES);
+    }
+
+    #[test]
+    fn unknown_levels_fall_back_to_info() {
+        assert_eq!(parse_level(" debug "), LevelFilter::Debug);
+        assert_eq!(parse_level("off"), LevelFilter::Off);
+        assert_eq!(parse_level("verbose"), LevelFilter::Info);
+    }
+}
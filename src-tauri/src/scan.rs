@@ -0,0 +1,280 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::encoder;
+
+/// Options controlling recursive folder scanning.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Maximum directory depth below the scan root (0 = root only).
+    pub max_depth: usize,
+    /// Files larger than this many bytes are left out. None = unlimited.
+    pub max_file_size: Option<u64>,
+    /// Extensions to include instead of the built-in text list. None = built-in list.
+    pub extensions: Option<Vec<String>>,
+    /// Paths (relative to the scan root) matching any of these are skipped;
+    /// a matching directory is not descended into.
+    pub exclude: Option<GlobSet>,
+    /// Skip paths ignored by .gitignore files (and the `.git` directory).
+    pub respect_gitignore: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 32,
+            max_file_size: None,
+            extensions: None,
+            exclude: None,
+            respect_gitignore: false,
+        }
+    }
+}
+
+/// Compile exclude patterns such as `**/node_modules/**` or `*.min.js`.
+pub fn build_exclude_set(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| format!("Invalid exclude pattern {}: {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Failed to build exclude patterns: {}", e))
+}
+
+/// Recursively collect text files under `dir`.
+/// Directories are tracked by canonical path, so symlink loops are visited only once.
+/// With `respect_gitignore`, the walk is delegated to `collect_with_gitignore`.
+pub fn collect_text_files(dir: &Path, options: &ScanOptions) -> Result<Vec<String>, String> {
+    if options.respect_gitignore {
+        return collect_with_gitignore(dir, options);
+    }
+
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    collect_dir(dir, dir, 0, options, &mut visited, &mut files)?;
+    Ok(files)
+}
+
+/// Walk `dir` with the `ignore` crate so paths excluded by .gitignore files (and
+/// the `.git` directory itself) are skipped. Hidden files are still scanned, and
+/// symlinks are not followed.
+fn collect_with_gitignore(dir: &Path, options: &ScanOptions) -> Result<Vec<String>, String> {
+    let root = dir.to_path_buf();
+    let exclude_options = options.clone();
+    let walker = WalkBuilder::new(dir)
+        // ignore counts the root itself as depth 0
+        .max_depth(Some(options.max_depth + 1))
+        .hidden(false)
+        .git_ignore(true)
+        .require_git(false)
+        .filter_entry(move |entry| {
+            entry.file_name() != ".git" && !is_excluded(&root, entry.path(), &exclude_options)
+        })
+        .build();
+
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = entry.map_err(|e| format!("Failed to read directory: {}", e))?;
+        let is_file = entry.file_type().map(|t| t.is_file()).unwrap_or(false);
+        if is_file && is_wanted_file(entry.path(), options) {
+            if let Some(s) = entry.path().to_str() {
+                files.push(s.to_string());
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn collect_dir(
+    root: &Path,
+    dir: &Path,
+    depth: usize,
+    options: &ScanOptions,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<String>,
+) -> Result<(), String> {
+    // Skip directories already seen through another path (symlink cycles)
+    let canonical = fs::canonicalize(dir)
+        .map_err(|e| format!("Failed to resolve directory: {}", e))?;
+    if !visited.insert(canonical) {
+        return Ok(());
+    }
+
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory: {}", e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("{}", e))?;
+        let path = entry.path();
+        if is_excluded(root, &path, options) {
+            continue;
+        }
+        if path.is_dir() {
+            if depth < options.max_depth {
+                collect_dir(root, &path, depth + 1, options, visited, files)?;
+            }
+        } else if is_wanted_file(&path, options) {
+            if let Some(s) = path.to_str() {
+                files.push(s.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the file has one of the known text extensions.
+pub fn has_text_extension(path: &Path) -> bool {
+    const TEXT_EXTENSIONS: &[&str] = &[
+        "csv", "tsv", "txt", "xml", "xsl", "xslt", "json", "htm", "html", "dat", "log",
+        "md", "yml", "yaml", "toml", "ini", "cfg", "conf", "properties",
+        "sql", "sh", "bat", "cmd", "ps1",
+    ];
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        TEXT_EXTENSIONS.contains(&ext.to_lowercase().as_str())
+    } else {
+        false
+    }
+}
+
+/// Whether a scanned file belongs in the results: not binary, within the size
+/// limit, and with a known (or explicitly requested) text extension.
+fn is_wanted_file(path: &Path, options: &ScanOptions) -> bool {
+    if encoder::is_binary_file(path) {
+        return false;
+    }
+    if let Some(limit) = options.max_file_size {
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if size > limit {
+            return false;
+        }
+    }
+    match &options.extensions {
+        Some(extensions) => has_extension(path, extensions),
+        None => has_text_extension(path),
+    }
+}
+
+/// Whether the file's extension is one of `extensions` (case-insensitive, leading '.' optional).
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    extensions
+        .iter()
+        .any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(ext))
+}
+
+fn is_excluded(root: &Path, path: &Path, options: &ScanOptions) -> bool {
+    let Some(exclude) = &options.exclude else {
+        return false;
+    };
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    exclude.is_match(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("encodingman_scan_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loop_terminates() {
+        let dir = test_dir("symlink_loop");
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        fs::write(dir.join("a/b/data.csv"), "1,2\n").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("a/b/loop")).unwrap();
+
+        let files = collect_text_files(&dir, &ScanOptions::default()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("data.csv"));
+    }
+
+    #[test]
+    fn max_depth_is_respected() {
+        let dir = test_dir("max_depth");
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        fs::write(dir.join("top.csv"), "1\n").unwrap();
+        fs::write(dir.join("a/one.csv"), "1\n").unwrap();
+        fs::write(dir.join("a/b/two.csv"), "1\n").unwrap();
+
+        let scan = |max_depth| {
+            let options = ScanOptions {
+                max_depth,
+                ..Default::default()
+            };
+            collect_text_files(&dir, &options).unwrap().len()
+        };
+        assert_eq!(scan(0), 1);
+        assert_eq!(scan(1), 2);
+        assert_eq!(scan(2), 3);
+    }
+
+    #[test]
+    fn max_file_size_keeps_files_at_the_limit() {
+        let dir = test_dir("max_file_size");
+        fs::write(dir.join("under.csv"), "0123456789").unwrap();
+        fs::write(dir.join("over.csv"), "01234567890").unwrap();
+
+        let options = ScanOptions {
+            max_file_size: Some(10),
+            ..Default::default()
+        };
+        let files = collect_text_files(&dir, &options).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("under.csv"));
+    }
+
+    #[test]
+    fn extension_override_and_exclude_globs() {
+        let dir = test_dir("filters");
+        fs::create_dir_all(dir.join("node_modules/pkg")).unwrap();
+        fs::write(dir.join("data.csv"), "1\n").unwrap();
+        fs::write(dir.join("app.log"), "1\n").unwrap();
+        fs::write(dir.join("node_modules/pkg/index.log"), "1\n").unwrap();
+
+        let options = ScanOptions {
+            extensions: Some(vec![".LOG".to_string()]),
+            exclude: Some(build_exclude_set(&["node_modules/**".to_string()]).unwrap()),
+            ..Default::default()
+        };
+        let files = collect_text_files(&dir, &options).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("app.log"));
+
+        assert!(build_exclude_set(&["[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn gitignored_paths_are_skipped_when_asked() {
+        let dir = test_dir("gitignore");
+        fs::create_dir_all(dir.join("build")).unwrap();
+        fs::write(dir.join(".gitignore"), "build/\n*.tmp.csv\n").unwrap();
+        fs::write(dir.join("keep.csv"), "1\n").unwrap();
+        fs::write(dir.join("scratch.tmp.csv"), "1\n").unwrap();
+        fs::write(dir.join("build/out.csv"), "1\n").unwrap();
+
+        let options = ScanOptions {
+            respect_gitignore: true,
+            ..Default::default()
+        };
+        let files = collect_text_files(&dir, &options).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("keep.csv"));
+
+        let all = collect_text_files(&dir, &ScanOptions::default()).unwrap();
+        assert_eq!(all.len(), 3);
+    }
+}
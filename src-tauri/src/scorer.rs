@@ -1,113 +1,843 @@
 use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
 
 /// Score result for a single encoding candidate.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct EncodingScore {
     pub encoding_name: String,
     pub score: f64,
     pub replacement_count: usize,
-    pub japanese_char_count: usize,
+    pub cjk_char_count: usize,
     pub total_chars: usize,
+    /// Byte sequences that can't be valid lead/trail pairs in this encoding
+    /// (Shift_JIS and EUC-JP only; 0 for others).
+    pub structural_errors: usize,
 }
 
-/// Candidate encodings to try for Japanese text detection.
-const CANDIDATE_ENCODINGS: &[&str] = &[
+/// Default candidate encodings to try for CJK text detection.
+/// Japanese encodings come first so they win ties against the Chinese ones.
+pub const DEFAULT_CANDIDATE_ENCODINGS: &[&str] = &[
     "Shift_JIS",
     "EUC-JP",
     "ISO-2022-JP",
     "UTF-8",
     "UTF-16LE",
     "UTF-16BE",
+    "GBK",
+    "Big5",
     "windows-1252",
+    "ISO-8859-15",
+    "windows-1250",
+    "windows-1251",
 ];
 
-/// Score all candidate encodings against raw bytes.
-/// Returns scores sorted descending (best first).
-pub fn score_all_encodings(data: &[u8]) -> Vec<EncodingScore> {
-    let mut scores: Vec<EncodingScore> = CANDIDATE_ENCODINGS
+/// Single-byte codepages, which decode almost any bytes without errors and so
+/// are told apart by how plausible the decoded characters are.
+/// (encoding_rs treats ISO-8859-1 as windows-1252, so it needs no entry of its own.)
+const SINGLE_BYTE_ENCODINGS: &[&str] = &["windows-1252", "ISO-8859-15", "windows-1250", "windows-1251"];
+
+/// Weights of the score formula. Missing fields in config fall back to the defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringWeights {
+    /// Reward for few U+FFFD replacement characters.
+    pub replacement: f64,
+    /// Reward for few control characters.
+    pub valid: f64,
+    /// Reward for CJK content (the Japanese/Chinese/Korean bonus).
+    pub cjk: f64,
+    /// Reward for plausible accented/Cyrillic letters in single-byte decodes.
+    pub letters: f64,
+    /// Constant added to every score.
+    pub base: f64,
+    /// Penalty when the decoder reported malformed input.
+    pub error_penalty: f64,
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self {
+            replacement: 0.4,
+            valid: 0.2,
+            cjk: 0.3,
+            letters: 0.15,
+            base: 0.1,
+            error_penalty: 0.3,
+        }
+    }
+}
+
+impl ScoringWeights {
+    /// Negative weights become 0, and everything is scaled so that
+    /// replacement + valid + cjk + base = 1, keeping scores around 0..1.
+    /// If those are all 0 the defaults are used.
+    pub fn normalized(&self) -> Self {
+        let w = |v: f64| if v.is_finite() { v.max(0.0) } else { 0.0 };
+        let replacement = w(self.replacement);
+        let valid = w(self.valid);
+        let cjk = w(self.cjk);
+        let base = w(self.base);
+        let total = replacement + valid + cjk + base;
+        if total <= 0.0 {
+            return Self::default();
+        }
+        Self {
+            replacement: replacement / total,
+            valid: valid / total,
+            cjk: cjk / total,
+            letters: w(self.letters) / total,
+            base: base / total,
+            error_penalty: w(self.error_penalty) / total,
+        }
+    }
+}
+
+/// Sub-scores behind an `EncodingScore`, exposed for inspection.
+#[derive(Debug, Clone, Serialize)]
+pub struct EncodingScoreDetail {
+    pub score: EncodingScore,
+    pub replacement_ratio: f64,
+    pub valid_ratio: f64,
+    pub cjk_ratio: f64,
+    pub error_penalty: f64,
+    /// How much the decode looks like natural Japanese (0.0 = not at all).
+    /// Only used to break near-ties between Japanese encodings.
+    pub naturalness: f64,
+}
+
+/// Japanese encodings whose near-ties are broken by `naturalness`.
+const JAPANESE_ENCODINGS: &[&str] = &["Shift_JIS", "EUC-JP", "ISO-2022-JP"];
+
+/// Scores this close to the best count as a tie.
+const NEAR_TIE_MARGIN: f64 = 0.05;
+
+/// Chinese encodings, whose decodes get a Han-only CJK-content bonus.
+const CHINESE_ENCODINGS: &[&str] = &["GBK", "gb18030", "Big5"];
+
+/// Korean encodings, whose decodes get a Hangul-only CJK-content bonus.
+const KOREAN_ENCODINGS: &[&str] = &["EUC-KR"];
+
+/// Score the given candidate encodings against raw bytes.
+/// Labels that encoding_rs doesn't recognize (and duplicates) are skipped.
+/// Returns scores sorted descending (best first); earlier candidates win ties.
+/// When the best result is Japanese and other Japanese encodings are within
+/// `NEAR_TIE_MARGIN`, the most natural-looking decode among them goes first.
+pub fn score_all_encodings(
+    data: &[u8],
+    candidates: &[String],
+    weights: &ScoringWeights,
+) -> Vec<EncodingScore> {
+    let weights = weights.normalized();
+    let mut seen: Vec<&'static Encoding> = Vec::new();
+    let mut details: Vec<EncodingScoreDetail> = candidates
         .iter()
-        .filter_map(|name| {
-            let encoding = Encoding::for_label(name.as_bytes())?;
-            Some(score_encoding(data, encoding, name))
+        .filter_map(|label| {
+            let encoding = Encoding::for_label(label.trim().as_bytes())?;
+            if seen.contains(&encoding) {
+                return None;
+            }
+            seen.push(encoding);
+            Some(score_encoding_with_detail(data, encoding, &weights))
         })
         .collect();
 
-    scores.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
-    scores
+    details.sort_by(|a, b| {
+        b.score
+            .score
+            .partial_cmp(&a.score.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    break_japanese_near_tie(&mut details);
+    details.into_iter().map(|d| d.score).collect()
 }
 
-/// Pick the best encoding for the given data.
-pub fn best_encoding(data: &[u8]) -> EncodingScore {
-    let scores = score_all_encodings(data);
+/// Move the most natural Japanese decode to the front when it nearly ties the best.
+fn break_japanese_near_tie(details: &mut [EncodingScoreDetail]) {
+    let Some(top) = details.first() else {
+        return;
+    };
+    if !JAPANESE_ENCODINGS.contains(&top.score.encoding_name.as_str()) {
+        return;
+    }
+    let top_score = top.score.score;
+
+    let mut best_index = 0usize;
+    for (i, detail) in details.iter().enumerate().skip(1) {
+        if top_score - detail.score.score > NEAR_TIE_MARGIN {
+            break;
+        }
+        if JAPANESE_ENCODINGS.contains(&detail.score.encoding_name.as_str())
+            && detail.naturalness > details[best_index].naturalness
+        {
+            best_index = i;
+        }
+    }
+    details[..=best_index].rotate_right(1);
+}
+
+/// Score a single encoding by label and return the sub-scores.
+/// Returns None if encoding_rs doesn't recognize the label.
+pub fn score_encoding_detailed(
+    data: &[u8],
+    label: &str,
+    weights: &ScoringWeights,
+) -> Option<EncodingScoreDetail> {
+    let encoding = Encoding::for_label(label.trim().as_bytes())?;
+    Some(score_encoding_with_detail(data, encoding, &weights.normalized()))
+}
+
+/// Pick the best encoding for the given data among the candidates.
+pub fn best_encoding(data: &[u8], candidates: &[String], weights: &ScoringWeights) -> EncodingScore {
+    let scores = score_all_encodings(data, candidates, weights);
     scores.into_iter().next().unwrap_or(EncodingScore {
         encoding_name: "UTF-8".to_string(),
         score: 0.0,
         replacement_count: 0,
-        japanese_char_count: 0,
+        cjk_char_count: 0,
         total_chars: 0,
+        structural_errors: 0,
+    })
+}
+
+/// Chunk size used by `detect_mixed_encoding`; chunks are extended to the next line break.
+const MIXED_CHUNK_SIZE: usize = 1024;
+
+/// Chunks with fewer non-ASCII bytes than this say too little about their encoding.
+const MIXED_MIN_NON_ASCII: usize = 16;
+
+/// Heuristic check for files made of sections in different encodings (e.g. a Shift_JIS
+/// log with UTF-8 lines appended). The data is split into line-aligned chunks and each
+/// chunk with enough non-ASCII bytes gets its own best encoding. Returns true when the
+/// chunks disagree and some chunk's encoding can't decode another chunk cleanly, i.e.
+/// no single chunk's choice would convert the whole file without damage.
+/// Chunks are scored with the configured `candidates` and `weights`, like the whole file.
+/// Data containing NUL bytes (UTF-16/32) is never reported.
+pub fn detect_mixed_encoding(data: &[u8], candidates: &[String], weights: &ScoringWeights) -> bool {
+    if data.contains(&0) {
+        return false;
+    }
+
+    let mut chunks: Vec<(&[u8], &'static Encoding)> = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let start = MIXED_CHUNK_SIZE.min(rest.len());
+        let end = rest[start..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(rest.len(), |i| start + i + 1);
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+
+        if chunk.iter().filter(|b| !b.is_ascii()).count() < MIXED_MIN_NON_ASCII {
+            continue;
+        }
+        let best = if std::str::from_utf8(chunk).is_ok() {
+            "UTF-8".to_string()
+        } else {
+            best_encoding(chunk, candidates, weights).encoding_name
+        };
+        if let Some(encoding) = Encoding::for_label(best.as_bytes()) {
+            chunks.push((chunk, encoding));
+        }
+    }
+
+    chunks.iter().any(|&(_, encoding)| {
+        chunks.iter().any(|&(other, other_encoding)| {
+            other_encoding != encoding && encoding.decode_without_bom_handling(other).1
+        })
     })
 }
 
-fn score_encoding(data: &[u8], encoding: &'static Encoding, name: &str) -> EncodingScore {
+/// `weights` must already be normalized.
+fn score_encoding_with_detail(
+    data: &[u8],
+    encoding: &'static Encoding,
+    weights: &ScoringWeights,
+) -> EncodingScoreDetail {
+    let name = encoding.name();
     let (decoded, _, had_errors) = encoding.decode(data);
 
     let total_chars = decoded.chars().count();
     if total_chars == 0 {
-        return EncodingScore {
-            encoding_name: name.to_string(),
-            score: 0.0,
-            replacement_count: 0,
-            japanese_char_count: 0,
-            total_chars: 0,
+        return EncodingScoreDetail {
+            score: EncodingScore {
+                encoding_name: name.to_string(),
+                score: 0.0,
+                replacement_count: 0,
+                cjk_char_count: 0,
+                total_chars: 0,
+                structural_errors: 0,
+            },
+            replacement_ratio: 0.0,
+            valid_ratio: 0.0,
+            cjk_ratio: 0.0,
+            error_penalty: 0.0,
+            naturalness: 0.0,
         };
     }
 
     let mut replacement_count = 0usize;
-    let mut japanese_count = 0usize;
+    let mut han_count = 0usize;
+    let mut kana_count = 0usize;
+    let mut hangul_count = 0usize;
+    let mut cjk_symbol_count = 0usize;
     let mut control_count = 0usize;
 
     for ch in decoded.chars() {
         if ch == '\u{FFFD}' {
             replacement_count += 1;
         }
-        if is_japanese_char(ch) {
-            japanese_count += 1;
+        if is_cjk_han_char(ch) {
+            han_count += 1;
+        } else if is_kana_char(ch) {
+            kana_count += 1;
+        } else if is_hangul_char(ch) {
+            hangul_count += 1;
+        } else if is_cjk_symbol_char(ch) {
+            cjk_symbol_count += 1;
         }
         if ch.is_control() && ch != '\n' && ch != '\r' && ch != '\t' {
             control_count += 1;
         }
     }
 
+    // Chinese/Korean decodes outside the core repertoire are rare characters,
+    // which is what Latin-1 accented letters turn into when read as GBK or EUC-KR
+    let rare_count = if CHINESE_ENCODINGS.contains(&name) {
+        han_count.saturating_sub(core_pairs(data, name))
+    } else if KOREAN_ENCODINGS.contains(&name) {
+        hangul_count.saturating_sub(core_pairs(data, name))
+    } else {
+        0
+    };
+
+    // CJK-content bonus, judged by what is plausible for this encoding's language
+    let cjk_content = if CHINESE_ENCODINGS.contains(&name) {
+        // Kana never legitimately appears in Chinese text, and Shift_JIS/EUC-JP bytes
+        // "cleanly" decode as rare extension-area hanzi, so only core-repertoire Han counts
+        han_count.min(core_pairs(data, name)) as f64 + cjk_symbol_count as f64
+    } else if KOREAN_ENCODINGS.contains(&name) {
+        // Same idea for Korean: Shift_JIS lead bytes land in the UHC extension Hangul
+        hangul_count.min(core_pairs(data, name)) as f64 + cjk_symbol_count as f64
+    } else if kana_count == 0 {
+        // Japanese text without a single kana is more likely Chinese bytes
+        han_count as f64 * 0.5 + cjk_symbol_count as f64
+    } else {
+        (han_count + kana_count + cjk_symbol_count) as f64
+    };
+    let cjk_count = han_count + kana_count + hangul_count + cjk_symbol_count;
+
     let replacement_ratio = replacement_count as f64 / total_chars as f64;
     let valid_ratio = (total_chars.saturating_sub(control_count)) as f64 / total_chars as f64;
-    let japanese_ratio = japanese_count as f64 / total_chars as f64;
+    let cjk_ratio = cjk_content / total_chars as f64;
 
     // Penalize heavily if encoding_rs reported errors
-    let error_penalty = if had_errors { 0.3 } else { 0.0 };
+    let error_penalty = if had_errors { weights.error_penalty } else { 0.0 };
+
+    // Single-byte decodes earn a (smaller) content bonus for plausible
+    // accented or Cyrillic letters, mirroring the CJK bonus
+    let (single_byte_penalty, letter_content) = if SINGLE_BYTE_ENCODINGS.contains(&name) {
+        single_byte_plausibility(&decoded, total_chars)
+    } else {
+        (0.0, 0)
+    };
+    let letter_ratio = letter_content as f64 / total_chars as f64;
+
+    let stray_penalty = stray_cjk_chars(&decoded) as f64 / total_chars as f64 * 2.0;
+    let rare_penalty = rare_count as f64 / total_chars as f64;
+
+    // encoding_rs resynchronizes after a bad byte and may decode the rest cleanly,
+    // so broken lead/trail structure is counted on the raw bytes as well
+    let structural_errors = structural_errors(data, name);
+    let structural_penalty = structural_errors as f64 / total_chars as f64 * 2.0;
+
+    // 7-bit data always decodes cleanly as ISO-2022-JP; without its escape sequences
+    // it is just ASCII and must not compete with UTF-8
+    let iso2022_penalty = if name == "ISO-2022-JP" && !has_iso2022jp_escapes(data) {
+        0.5
+    } else {
+        0.0
+    };
+
+    // Real UTF-16 text has NUL bytes (line breaks, ASCII); without any, a UTF-16 decode
+    // is just ASCII byte pairs read as ideographs, which must not outscore Latin codepages
+    let utf16_penalty = if name.starts_with("UTF-16") && !data.contains(&0) {
+        0.3
+    } else {
+        0.0
+    };
+
+    let score = (1.0 - replacement_ratio) * weights.replacement
+        + valid_ratio * weights.valid
+        + cjk_ratio * weights.cjk
+        + letter_ratio * weights.letters
+        + weights.base
+        - error_penalty
+        - single_byte_penalty
+        - stray_penalty
+        - rare_penalty
+        - structural_penalty
+        - iso2022_penalty
+        - utf16_penalty;
+
+    EncodingScoreDetail {
+        score: EncodingScore {
+            encoding_name: name.to_string(),
+            // Rounding can push a perfect decode just past 1.0
+            score: score.clamp(0.0, 1.0),
+            replacement_count,
+            cjk_char_count: cjk_count,
+            total_chars,
+            structural_errors,
+        },
+        replacement_ratio,
+        valid_ratio,
+        cjk_ratio,
+        error_penalty,
+        naturalness: japanese_naturalness(&decoded, total_chars),
+    }
+}
 
-    let score = (1.0 - replacement_ratio) * 0.4
-        + valid_ratio * 0.2
-        + japanese_ratio * 0.3
-        + 0.1
-        - error_penalty;
+/// Count CJK characters that stand alone where real CJK text wouldn't: no CJK
+/// neighbour on either side, and either halfwidth katakana (which comes in runs)
+/// or glued to an ASCII letter. These are stray high bytes from Latin text.
+fn stray_cjk_chars(decoded: &str) -> usize {
+    let is_halfwidth = |ch: char| ('\u{FF61}'..='\u{FF9F}').contains(&ch);
+    let is_cjk = |ch: char| {
+        is_halfwidth(ch) || is_kana_char(ch) || is_cjk_han_char(ch) || is_hangul_char(ch)
+    };
 
-    EncodingScore {
-        encoding_name: name.to_string(),
-        score: score.max(0.0),
-        replacement_count,
-        japanese_char_count: japanese_count,
-        total_chars,
+    let chars: Vec<char> = decoded.chars().collect();
+    (0..chars.len())
+        .filter(|&i| is_cjk(chars[i]))
+        .filter(|&i| {
+            let prev = i.checked_sub(1).map(|j| chars[j]);
+            let next = chars.get(i + 1).copied();
+            let isolated = !prev.is_some_and(is_cjk) && !next.is_some_and(is_cjk);
+            let touches_latin = prev.is_some_and(|c| c.is_ascii_alphabetic())
+                || next.is_some_and(|c| c.is_ascii_alphabetic());
+            isolated && (is_halfwidth(chars[i]) || touches_latin)
+        })
+        .count()
+}
+
+/// Judge a single-byte decode. Returns (penalty 0.0-0.5, plausible non-ASCII letters).
+/// The penalty covers C1 controls (0x80-0x9F) and rarely used Latin-1 symbols
+/// (the slots ISO-8859-15 reassigned, e.g. ¤ where € belongs), plus implausible
+/// words: Cyrillic mixed with Latin letters, or Latin words made only of accented
+/// letters. Non-ASCII letters in the remaining words count as content.
+fn single_byte_plausibility(decoded: &str, total_chars: usize) -> (f64, usize) {
+    let is_cyrillic = |ch: char| ('\u{0400}'..='\u{04FF}').contains(&ch);
+    let suspicious_chars = decoded
+        .chars()
+        .filter(|&ch| matches!(ch, '\u{80}'..='\u{9F}' | '¤' | '¦' | '¨' | '´' | '¸' | '¼' | '½' | '¾'))
+        .count();
+
+    let mut words = 0usize;
+    let mut implausible_words = 0usize;
+    let mut plausible_letters = 0usize;
+    for word in decoded.split(|ch: char| !ch.is_alphabetic()).filter(|w| !w.is_empty()) {
+        words += 1;
+        let has_ascii = word.chars().any(|ch| ch.is_ascii_alphabetic());
+        let has_cyrillic = word.chars().any(is_cyrillic);
+        let has_accented = word.chars().any(|ch| !ch.is_ascii() && !is_cyrillic(ch));
+        let mixed_script = has_cyrillic && (has_ascii || has_accented);
+        let all_accented = !has_ascii && has_accented && word.chars().count() >= 3;
+        if mixed_script || all_accented {
+            implausible_words += 1;
+        } else {
+            plausible_letters += word.chars().filter(|ch| !ch.is_ascii()).count();
+        }
     }
+
+    let char_penalty = suspicious_chars as f64 / total_chars as f64 * 2.0;
+    let word_penalty = if words == 0 {
+        0.0
+    } else {
+        implausible_words as f64 / words as f64 * 0.5
+    };
+    ((char_penalty + word_penalty).min(0.5), plausible_letters)
 }
 
-fn is_japanese_char(ch: char) -> bool {
+/// Weighted share of characters (and character pairs) typical of Japanese prose.
+/// Hiragana weighs most, then katakana and kanji; halfwidth katakana and
+/// punctuation weigh little, since misdecoded Shift_JIS/EUC-JP bytes produce them.
+/// Kanji or kana followed by hiragana (okurigana, particles) earns a bigram bonus.
+fn japanese_naturalness(decoded: &str, total_chars: usize) -> f64 {
+    let weight = |ch: char| match ch {
+        '\u{3040}'..='\u{309F}' => 1.0,
+        '\u{30A0}'..='\u{30FF}' => 0.8,
+        '\u{FF66}'..='\u{FF9F}' => 0.1,
+        _ if is_cjk_han_char(ch) => 0.6,
+        _ if is_cjk_symbol_char(ch) => 0.2,
+        _ => 0.0,
+    };
+    let is_hiragana = |ch: char| ('\u{3040}'..='\u{309F}').contains(&ch);
+
+    let mut total = 0.0;
+    let mut prev: Option<char> = None;
+    for ch in decoded.chars() {
+        total += weight(ch);
+        if let Some(p) = prev {
+            if is_hiragana(ch) && (is_cjk_han_char(p) || weight(p) >= 0.8) {
+                total += 0.5;
+            }
+        }
+        prev = Some(ch);
+    }
+    total / total_chars as f64
+}
+
+/// Count double-byte pairs that fall in the common area of a Chinese/Korean encoding
+/// (GB2312 level-1 hanzi for GBK, the frequently used hanzi block for Big5,
+/// the KS X 1001 Hangul rows for EUC-KR). Cyrillic lowercase letters (0xE0-0xFF)
+/// read as GBK/Big5 land in the less common blocks, so they don't count.
+fn core_pairs(data: &[u8], name: &str) -> usize {
+    let is_core: fn(u8, u8) -> bool = match name {
+        "GBK" | "gb18030" => {
+            |lead, trail| (0xB0..=0xD7).contains(&lead) && (0xA1..=0xFE).contains(&trail)
+        }
+        "EUC-KR" => |lead, trail| (0xB0..=0xC8).contains(&lead) && (0xA1..=0xFE).contains(&trail),
+        "Big5" => |lead, trail| {
+            (0xA4..=0xC6).contains(&lead)
+                && ((0x40..=0x7E).contains(&trail) || (0xA1..=0xFE).contains(&trail))
+        },
+        _ => return 0,
+    };
+
+    let mut count = 0usize;
+    let mut i = 0usize;
+    while i < data.len() {
+        if data[i] < 0x80 {
+            i += 1;
+            continue;
+        }
+        if i + 1 < data.len() && is_core(data[i], data[i + 1]) {
+            count += 1;
+        }
+        i += 2;
+    }
+    count
+}
+
+/// Count byte sequences that are structurally invalid in Shift_JIS or EUC-JP,
+/// judged only by byte ranges (not by whether the pair maps to a character).
+/// Shift_JIS: lead 0x81-0x9F/0xE0-0xFC needs a trail of 0x40-0x7E/0x80-0xFC, and
+/// 0xA1-0xDF stands alone. EUC-JP: lead 0xA1-0xFE needs a trail of 0xA1-0xFE, with
+/// 0x8E (one trail) and 0x8F (two trails) as prefixes. Other encodings count 0.
+fn structural_errors(data: &[u8], name: &str) -> usize {
+    let mut errors = 0usize;
+    let mut i = 0usize;
+    let byte = |i: usize| data.get(i).copied();
+
+    match name {
+        "Shift_JIS" => {
+            let is_trail = |b: u8| (0x40..=0x7E).contains(&b) || (0x80..=0xFC).contains(&b);
+            while let Some(b) = byte(i) {
+                match b {
+                    0x00..=0x7F | 0xA1..=0xDF => i += 1,
+                    0x81..=0x9F | 0xE0..=0xFC if byte(i + 1).is_some_and(is_trail) => i += 2,
+                    _ => {
+                        errors += 1;
+                        i += 1;
+                    }
+                }
+            }
+        }
+        "EUC-JP" => {
+            let is_trail = |b: u8| (0xA1..=0xFE).contains(&b);
+            while let Some(b) = byte(i) {
+                match b {
+                    0x00..=0x7F => i += 1,
+                    0x8E if byte(i + 1).is_some_and(|t| (0xA1..=0xDF).contains(&t)) => i += 2,
+                    0x8F if byte(i + 1).is_some_and(is_trail)
+                        && byte(i + 2).is_some_and(is_trail) =>
+                    {
+                        i += 3
+                    }
+                    0xA1..=0xFE if byte(i + 1).is_some_and(is_trail) => i += 2,
+                    _ => {
+                        errors += 1;
+                        i += 1;
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    errors
+}
+
+/// Whether the data contains an ISO-2022-JP designation: ESC $ @ / ESC $ B
+/// (JIS X 0208) or ESC ( B / ESC ( J (back to ASCII / JIS X 0201 Roman).
+fn has_iso2022jp_escapes(data: &[u8]) -> bool {
+    data.windows(3)
+        .any(|w| matches!(w, [0x1B, b'$', b'@' | b'B'] | [0x1B, b'(', b'B' | b'J']))
+}
+
+fn is_kana_char(ch: char) -> bool {
     matches!(ch,
         '\u{3040}'..='\u{309F}'   // Hiragana
         | '\u{30A0}'..='\u{30FF}' // Katakana
-        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs (Kanji)
+        | '\u{FF66}'..='\u{FF9F}' // Halfwidth Katakana
+    )
+}
+
+fn is_hangul_char(ch: char) -> bool {
+    matches!(ch,
+        '\u{AC00}'..='\u{D7AF}'   // Hangul Syllables
+        | '\u{1100}'..='\u{11FF}' // Hangul Jamo
+        | '\u{3130}'..='\u{318F}' // Hangul Compatibility Jamo
+    )
+}
+
+fn is_cjk_symbol_char(ch: char) -> bool {
+    matches!(ch,
+        '\u{FF00}'..='\u{FFEF}'   // Halfwidth/Fullwidth forms
+        | '\u{3000}'..='\u{303F}' // CJK Symbols and Punctuation
+    )
+}
+
+/// Han ideographs shared by Chinese and Japanese text.
+fn is_cjk_han_char(ch: char) -> bool {
+    matches!(ch,
+        '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
         | '\u{3400}'..='\u{4DBF}' // CJK Extension A
         | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
-        | '\u{FF00}'..='\u{FFEF}' // Halfwidth/Fullwidth forms
-        | '\u{3000}'..='\u{303F}' // CJK Symbols and Punctuation
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_candidates() -> Vec<String> {
+        DEFAULT_CANDIDATE_ENCODINGS
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    fn candidate_index(name: &str) -> usize {
+        DEFAULT_CANDIDATE_ENCODINGS
+            .iter()
+            .position(|c| *c == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn every_candidate_is_scored_and_ties_keep_list_order() {
+        let scores = score_all_encodings(
+            b"id,name\n1,apple\n2,banana\n",
+            &default_candidates(),
+            &ScoringWeights::default(),
+        );
+        let mut names: Vec<&str> = scores.iter().map(|s| s.encoding_name.as_str()).collect();
+        for pair in scores.windows(2) {
+            if pair[0].score == pair[1].score {
+                assert!(
+                    candidate_index(&pair[0].encoding_name)
+                        < candidate_index(&pair[1].encoding_name)
+                );
+            }
+        }
+        names.sort_unstable();
+        let mut expected = DEFAULT_CANDIDATE_ENCODINGS.to_vec();
+        expected.sort_unstable();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn chinese_samples_pick_chinese_encodings() {
+        let (gbk, _, _) = encoding_rs::GBK.encode("简体中文的测试文件，包含常用汉字。");
+        assert_eq!(
+            best_encoding(&gbk, &default_candidates(), &ScoringWeights::default()).encoding_name,
+            "GBK"
+        );
+
+        let (big5, _, _) = encoding_rs::BIG5.encode("繁體中文的測試檔案，包含常用漢字。");
+        assert_eq!(
+            best_encoding(&big5, &default_candidates(), &ScoringWeights::default()).encoding_name,
+            "Big5"
+        );
+    }
+
+    #[test]
+    fn configured_candidates_add_korean_and_skip_unknown_labels() {
+        let text = "한국어 텍스트 파일입니다.";
+        let (euc_kr, _, _) = encoding_rs::EUC_KR.encode(text);
+        assert_ne!(
+            best_encoding(&euc_kr, &default_candidates(), &ScoringWeights::default()).encoding_name,
+            "EUC-KR"
+        );
+
+        // Candidates are in tie-break priority order, so a Korean user lists EUC-KR first
+        let mut candidates = default_candidates();
+        candidates.insert(0, "EUC-KR".to_string());
+        candidates.push("no-such-encoding".to_string());
+        let scores = score_all_encodings(&euc_kr, &candidates, &ScoringWeights::default());
+        assert_eq!(scores[0].encoding_name, "EUC-KR");
+        assert_eq!(scores.len(), DEFAULT_CANDIDATE_ENCODINGS.len() + 1);
+
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("こんにちは、日本語のテキストです。");
+        assert_eq!(
+            best_encoding(&sjis, &candidates, &ScoringWeights::default()).encoding_name,
+            "Shift_JIS"
+        );
+    }
+
+    #[test]
+    fn near_tie_between_shift_jis_and_euc_jp_goes_to_natural_decode() {
+        let (euc, _, _) = encoding_rs::EUC_JP.encode("ひらがなです");
+        let sjis = score_encoding_detailed(&euc, "Shift_JIS", &ScoringWeights::default()).unwrap();
+        let eucjp = score_encoding_detailed(&euc, "EUC-JP", &ScoringWeights::default()).unwrap();
+        assert!((sjis.score.score - eucjp.score.score).abs() <= NEAR_TIE_MARGIN);
+        assert!(eucjp.naturalness > sjis.naturalness);
+        // Shift_JIS comes first among the candidates, so only the tie-break puts EUC-JP ahead.
+        assert_eq!(
+            best_encoding(&euc, &default_candidates(), &ScoringWeights::default()).encoding_name,
+            "EUC-JP"
+        );
+
+        let (sjis_bytes, _, _) = encoding_rs::SHIFT_JIS.encode("テスト用のファイルです。");
+        assert_eq!(
+            best_encoding(
+                &sjis_bytes,
+                &default_candidates(),
+                &ScoringWeights::default()
+            )
+            .encoding_name,
+            "Shift_JIS"
+        );
+    }
+
+    #[test]
+    fn latin_codepages_are_told_apart() {
+        let (cyrillic, _, _) =
+            encoding_rs::WINDOWS_1251.encode("Привет, мир! Это тестовый файл на русском языке.");
+        assert_eq!(
+            best_encoding(&cyrillic, &default_candidates(), &ScoringWeights::default())
+                .encoding_name,
+            "windows-1251"
+        );
+
+        let (euro, _, _) =
+            encoding_rs::ISO_8859_15.encode("Prix : 25 € par personne, café compris.");
+        assert_eq!(
+            best_encoding(&euro, &default_candidates(), &ScoringWeights::default()).encoding_name,
+            "ISO-8859-15"
+        );
+
+        let (french, _, _) =
+            encoding_rs::WINDOWS_1252.encode("Le café est très bon à Paris, n'est-ce pas ?");
+        assert_eq!(
+            best_encoding(&french, &default_candidates(), &ScoringWeights::default()).encoding_name,
+            "windows-1252"
+        );
+    }
+
+    #[test]
+    fn weights_are_normalized() {
+        let weights = ScoringWeights {
+            replacement: 2.0,
+            valid: 1.0,
+            cjk: 1.0,
+            letters: 0.5,
+            base: 0.0,
+            error_penalty: -1.0,
+        }
+        .normalized();
+        assert_eq!(weights.replacement, 0.5);
+        assert_eq!(weights.valid, 0.25);
+        assert_eq!(weights.cjk, 0.25);
+        assert_eq!(weights.letters, 0.125);
+        assert_eq!(weights.error_penalty, 0.0);
+
+        let zero = ScoringWeights {
+            replacement: 0.0,
+            valid: 0.0,
+            cjk: 0.0,
+            base: f64::NAN,
+            ..ScoringWeights::default()
+        };
+        assert_eq!(zero.normalized(), ScoringWeights::default());
+    }
+
+    #[test]
+    fn zero_cjk_weight_changes_the_winner() {
+        let (euc, _, _) = encoding_rs::EUC_JP.encode("日本語");
+        let no_cjk = ScoringWeights {
+            cjk: 0.0,
+            ..ScoringWeights::default()
+        };
+        let with_cjk = best_encoding(&euc, &default_candidates(), &ScoringWeights::default());
+        let without_cjk = best_encoding(&euc, &default_candidates(), &no_cjk);
+        assert_ne!(with_cjk.encoding_name, without_cjk.encoding_name);
+    }
+
+    #[test]
+    fn structural_errors_count_invalid_lead_trail_pairs() {
+        // 0xA1 0xFE is a valid EUC-JP pair; 0xFE is never valid in Shift_JIS.
+        let data = [0xA1, 0xFE, 0xA4, 0xA2, 0xA1, 0xFE];
+        assert_eq!(structural_errors(&data, "Shift_JIS"), 2);
+        assert_eq!(structural_errors(&data, "EUC-JP"), 0);
+        assert_eq!(structural_errors(&data, "UTF-8"), 0);
+
+        let (euc, _, _) = encoding_rs::EUC_JP.encode("◯これは日本語の文章です。◯");
+        let scores = score_all_encodings(&euc, &default_candidates(), &ScoringWeights::default());
+        let by_name = |name: &str| scores.iter().find(|s| s.encoding_name == name).unwrap();
+        assert_eq!(by_name("EUC-JP").structural_errors, 0);
+        assert!(by_name("Shift_JIS").structural_errors > 0);
+        assert_eq!(scores[0].encoding_name, "EUC-JP");
+    }
+
+    #[test]
+    fn mixed_encoding_uses_the_configured_candidates() {
+        let line = "これは日本語のテキストです。ログの一行です。\n".repeat(40);
+        let mut data = encoding_rs::SHIFT_JIS.encode(&line).0.into_owned();
+        data.extend_from_slice(&encoding_rs::EUC_JP.encode(&line).0);
+        let weights = ScoringWeights::default();
+
+        assert!(detect_mixed_encoding(
+            &data,
+            &default_candidates(),
+            &weights
+        ));
+        // With a single candidate every chunk gets the same encoding
+        assert!(!detect_mixed_encoding(
+            &data,
+            &["EUC-JP".to_string()],
+            &weights
+        ));
+        let sjis_only = encoding_rs::SHIFT_JIS
+            .encode(&line.repeat(2))
+            .0
+            .into_owned();
+        assert!(!detect_mixed_encoding(
+            &sjis_only,
+            &default_candidates(),
+            &weights
+        ));
+    }
+
+    #[test]
+    fn ascii_without_escapes_is_not_iso2022jp() {
+        let weights = ScoringWeights::default();
+        let csv = "id,name,price\n1,apple,100\n2,banana,80\n".repeat(5);
+        assert!(!has_iso2022jp_escapes(csv.as_bytes()));
+        let utf8 = score_encoding_detailed(csv.as_bytes(), "UTF-8", &weights).unwrap();
+        let iso = score_encoding_detailed(csv.as_bytes(), "ISO-2022-JP", &weights).unwrap();
+        assert!(utf8.score.score > iso.score.score);
+        assert_ne!(
+            best_encoding(csv.as_bytes(), &default_candidates(), &weights).encoding_name,
+            "ISO-2022-JP"
+        );
+
+        let (jis, _, _) = encoding_rs::ISO_2022_JP.encode("これは日本語のメールです。");
+        assert!(has_iso2022jp_escapes(&jis));
+        assert_eq!(
+            best_encoding(&jis, &default_candidates(), &weights).encoding_name,
+            "ISO-2022-JP"
+        );
+    }
+}
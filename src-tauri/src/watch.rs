@@ -0,0 +1,223 @@
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::batch::{self, BatchFileResult, Destination};
+use crate::cache::DetectionCache;
+use crate::config::{self, AppConfig};
+use crate::{launcher, scan};
+
+/// How long a file must go without new events before it is converted,
+/// so files still being written aren't picked up half-way.
+const QUIET_PERIOD: Duration = Duration::from_millis(1500);
+
+/// How often the watcher thread wakes up to check for quiet files.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The running folder watch, stored in Tauri state.
+#[derive(Default)]
+pub struct WatchState(pub Mutex<Option<WatchHandle>>);
+
+/// A running watch. Dropping the notify watcher disconnects the event channel,
+/// which ends the worker thread.
+pub struct WatchHandle {
+    watcher: RecommendedWatcher,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl WatchHandle {
+    /// Stop watching and wait for the worker thread to finish.
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        drop(self.watcher);
+        let _ = self.thread.join();
+    }
+}
+
+/// Tracks when each path last changed and hands it out once it has been quiet long enough.
+#[derive(Debug, Default)]
+pub struct Debouncer {
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl Debouncer {
+    /// Record activity on `path` at `now`, restarting its quiet period.
+    pub fn touch(&mut self, path: PathBuf, now: Instant) {
+        self.pending.insert(path, now);
+    }
+
+    /// Remove and return every path that has been quiet for `quiet` as of `now`.
+    pub fn take_ready(&mut self, now: Instant, quiet: Duration) -> Vec<PathBuf> {
+        let ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, &last)| now.duration_since(last) >= quiet)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready
+    }
+}
+
+/// Start watching `folder` (non-recursively). Each new or rewritten text file is
+/// converted into the temp directory once it goes quiet, and `on_converted` is
+/// called with the result. Nothing is launched.
+pub fn start<F>(folder: &Path, on_converted: F) -> Result<WatchHandle, String>
+where
+    F: Fn(BatchFileResult) + Send + 'static,
+{
+    if !folder.is_dir() {
+        return Err(format!("Not a directory: {}", folder.display()));
+    }
+
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to create folder watcher: {}", e))?;
+    watcher
+        .watch(folder, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch folder: {}", e))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+    let thread = thread::spawn(move || {
+        let mut debouncer = Debouncer::default();
+        while !thread_stop.load(Ordering::Relaxed) {
+            match rx.recv_timeout(POLL_INTERVAL) {
+                Ok(Ok(event)) if is_write_event(&event.kind) => {
+                    let now = Instant::now();
+                    for path in event.paths {
+                        debouncer.touch(path, now);
+                    }
+                }
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            for path in debouncer.take_ready(Instant::now(), QUIET_PERIOD) {
+                // Reload per file so settings changed while watching take effect
+                let cfg = config::load_config();
+                if let Some(result) = handle_new_file(&path, &cfg) {
+                    on_converted(result);
+                }
+            }
+        }
+    });
+
+    Ok(WatchHandle {
+        watcher,
+        stop,
+        thread,
+    })
+}
+
+/// Whether the event means a file's content may have changed: it was created,
+/// written, or renamed into place. Metadata-only changes (touch, chmod) are ignored.
+/// `ModifyKind::Any` counts as a write because some backends (e.g. Windows) report
+/// every modification that way.
+fn is_write_event(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_)
+            | EventKind::Modify(ModifyKind::Data(_))
+            | EventKind::Modify(ModifyKind::Name(RenameMode::To))
+            | EventKind::Modify(ModifyKind::Any)
+    )
+}
+
+/// Convert a newly appeared file into the temp directory without launching it.
+/// Returns None for files the watcher should ignore: directories, files that
+/// vanished, non-text extensions, and our own `*_utf8.*` output.
+pub fn handle_new_file(path: &Path, cfg: &AppConfig) -> Option<BatchFileResult> {
+    if !path.is_file() || !scan::has_text_extension(path) {
+        return None;
+    }
+    let file_name = path.file_name().and_then(|n| n.to_str())?;
+    if launcher::is_converted_file_name(file_name) {
+        return None;
+    }
+
+    let destination = match launcher::resolve_temp_dir(cfg.temp_dir.as_deref()) {
+        Ok(temp_dir) => Destination::Temp(temp_dir),
+        Err(e) => {
+            return Some(BatchFileResult {
+                file_path: path.to_string_lossy().to_string(),
+                file_name: file_name.to_string(),
+                status: "error".to_string(),
+                detected_encoding: None,
+                error_message: Some(e),
+                replacement_count: 0,
+                lossy: false,
+                converted_path: None,
+            });
+        }
+    };
+
+    let file_path = path.to_str()?;
+    // Each event is a new or rewritten file, so a shared cache would never hit
+    let cache = DetectionCache::default();
+    Some(batch::process_file(file_path, cfg, &destination, &cache).result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, MetadataKind};
+
+    #[test]
+    fn metadata_changes_are_not_writes() {
+        let cases = [
+            (EventKind::Create(CreateKind::File), true),
+            (
+                EventKind::Modify(ModifyKind::Data(DataChange::Content)),
+                true,
+            ),
+            (EventKind::Modify(ModifyKind::Any), true),
+            (
+                EventKind::Modify(ModifyKind::Metadata(MetadataKind::WriteTime)),
+                false,
+            ),
+            (
+                EventKind::Modify(ModifyKind::Metadata(MetadataKind::Permissions)),
+                false,
+            ),
+        ];
+        for (kind, expected) in cases {
+            assert_eq!(is_write_event(&kind), expected, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn debouncer_waits_for_the_quiet_period() {
+        let start = Instant::now();
+        let quiet = Duration::from_millis(100);
+        let mut debouncer = Debouncer::default();
+        debouncer.touch(PathBuf::from("a.csv"), start);
+        debouncer.touch(PathBuf::from("b.csv"), start);
+        // b.csv is still being written
+        debouncer.touch(PathBuf::from("b.csv"), start + Duration::from_millis(80));
+
+        assert!(debouncer
+            .take_ready(start + Duration::from_millis(50), quiet)
+            .is_empty());
+        assert_eq!(
+            debouncer.take_ready(start + Duration::from_millis(120), quiet),
+            vec![PathBuf::from("a.csv")]
+        );
+        assert_eq!(
+            debouncer.take_ready(start + Duration::from_millis(200), quiet),
+            vec![PathBuf::from("b.csv")]
+        );
+        assert!(debouncer
+            .take_ready(start + Duration::from_secs(10), quiet)
+            .is_empty());
+    }
+}
@@ -1,14 +1,22 @@
 import { useEffect, useState, useCallback } from "react";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { getCurrentWebview } from "@tauri-apps/api/webview";
+import { listen } from "@tauri-apps/api/event";
 import { open } from "@tauri-apps/plugin-dialog";
 import { check, type Update } from "@tauri-apps/plugin-updater";
 import { relaunch } from "@tauri-apps/plugin-process";
 import ConvertView from "./components/ConvertView";
 import Settings from "./components/Settings";
 import BatchView from "./components/BatchView";
-import type { ConvertResult } from "./lib/tauri-commands";
-import { detectAndConvert, scanFolder } from "./lib/tauri-commands";
+import type { ConvertProgress, ConvertResult } from "./lib/tauri-commands";
+import {
+  detectAndConvert,
+  getConfig,
+  openConvertedFile,
+  recoverDoubleEncoding,
+  scanFolder,
+  updateConfig,
+} from "./lib/tauri-commands";
 
 type View = "loading" | "convert" | "settings" | "done" | "idle" | "batch";
 type UpdateBanner =
@@ -25,16 +33,21 @@ export default function App() {
   const [batchPaths, setBatchPaths] = useState<string[]>([]);
   const [isDragOver, setIsDragOver] = useState(false);
   const [updateBanner, setUpdateBanner] = useState<UpdateBanner>(null);
+  const [convertPercent, setConvertPercent] = useState<number | null>(null);
 
   const processFile = useCallback(async (path: string) => {
     setView("loading");
     setError(null);
+    setConvertPercent(null);
     try {
       const convertResult = await detectAndConvert(path);
       setResult(convertResult);
 
       if (convertResult.auto_converted) {
         setView("done");
+        // Keep the window open so the user can see why the app didn't start,
+        // or repair a file that looks double-encoded
+        if (convertResult.launch_error || convertResult.double_encoding_preview.length > 0) return;
         setTimeout(async () => {
           await getCurrentWindow().close();
         }, 1500);
@@ -88,6 +101,17 @@ export default function App() {
     }
   }
 
+  // Large files are converted in chunks and report progress
+  useEffect(() => {
+    const unlisten = listen<ConvertProgress>("convert-progress", (event) => {
+      const { bytes_processed, total_bytes } = event.payload;
+      setConvertPercent(total_bytes > 0 ? Math.min(100, Math.round((bytes_processed / total_bytes) * 100)) : 0);
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
   // Tauri v2 drag-and-drop event listener
   useEffect(() => {
     let unlisten: (() => void) | undefined;
@@ -164,6 +188,32 @@ export default function App() {
     }
   }
 
+  async function handleResetDefaultApp() {
+    if (!result) return;
+    try {
+      const config = await getConfig();
+      await updateConfig({ ...config, default_app: "" });
+      await openConvertedFile(result.temp_file_path ?? result.original_path);
+      await getCurrentWindow().close();
+    } catch (e) {
+      setError(String(e));
+    }
+  }
+
+  async function handleRecoverDoubleEncoding() {
+    if (!result) return;
+    try {
+      const opened = await recoverDoubleEncoding(result.original_path);
+      if (opened.launch_error) {
+        setError(`修復しましたが開けませんでした: ${opened.launch_error}`);
+        return;
+      }
+      await getCurrentWindow().close();
+    } catch (e) {
+      setError(String(e));
+    }
+  }
+
   async function closeWindow() {
     await getCurrentWindow().close();
   }
@@ -173,7 +223,9 @@ export default function App() {
     return (
       <div className="flex flex-col items-center justify-center h-screen gap-3">
         <div className="w-10 h-10 border-3 border-sky-400 border-t-transparent rounded-full animate-spin" />
-        <div className="text-slate-400 text-sm">エンコード判定中...</div>
+        <div className="text-slate-400 text-sm">
+          {convertPercent === null ? "エンコード判定中..." : `変換中... ${convertPercent}%`}
+        </div>
       </div>
     );
   }
@@ -196,9 +248,45 @@ export default function App() {
             </>
           )}
         </div>
-        <div className="text-xs text-slate-500">
-          アプリケーションで開きました
-        </div>
+        {result?.launch_error ? (
+          <div className="flex flex-col items-center gap-2 max-w-sm text-center">
+            <div className="text-xs text-amber-400">
+              アプリケーションを起動できませんでした: {result.launch_error}
+            </div>
+            <div className="text-xs text-slate-500 break-all select-all">
+              {result.temp_file_path ?? result.original_path}
+            </div>
+            <div className="text-xs text-slate-500">
+              上記のファイルを手動で開くか、システムの既定アプリに戻してください
+            </div>
+            <button
+              onClick={handleResetDefaultApp}
+              className="px-3 py-1 rounded bg-sky-600 hover:bg-sky-500 text-xs font-medium transition-colors"
+            >
+              既定アプリに戻して開く
+            </button>
+            {error && <div className="text-xs text-red-400">{error}</div>}
+          </div>
+        ) : (
+          <div className="text-xs text-slate-500">
+            アプリケーションで開きました
+          </div>
+        )}
+        {result && result.double_encoding_preview.length > 0 && (
+          <div className="flex flex-col items-center gap-2 max-w-sm">
+            <div className="text-xs text-amber-400">{result.explanation}</div>
+            <pre className="w-full bg-slate-800 rounded px-3 py-2 text-xs text-slate-300 overflow-x-auto">
+              {result.double_encoding_preview.join("\n")}
+            </pre>
+            <button
+              onClick={handleRecoverDoubleEncoding}
+              className="px-3 py-1 rounded bg-sky-600 hover:bg-sky-500 text-xs font-medium transition-colors"
+            >
+              文字化けを修復して開く
+            </button>
+            {!result.launch_error && error && <div className="text-xs text-red-400">{error}</div>}
+          </div>
+        )}
         {/* Manual override button */}
         {result && !result.is_binary && result.original_path && (
           <button
@@ -1,6 +1,12 @@
 import { useEffect, useState } from "react";
-import type { BatchResult } from "../lib/tauri-commands";
-import { batchConvert } from "../lib/tauri-commands";
+import { listen } from "@tauri-apps/api/event";
+import { save } from "@tauri-apps/plugin-dialog";
+import type { BatchProgress, BatchResult } from "../lib/tauri-commands";
+import {
+  batchConvert,
+  cancelBatch,
+  exportBatchReport,
+} from "../lib/tauri-commands";
 
 interface BatchViewProps {
   filePaths: string[];
@@ -11,6 +17,16 @@ export default function BatchView({ filePaths, onClose }: BatchViewProps) {
   const [result, setResult] = useState<BatchResult | null>(null);
   const [processing, setProcessing] = useState(true);
   const [error, setError] = useState<string | null>(null);
+  const [doneCount, setDoneCount] = useState(0);
+
+  useEffect(() => {
+    const unlisten = listen<BatchProgress>("batch-progress", () => {
+      setDoneCount((n) => n + 1);
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
 
   useEffect(() => {
     async function run() {
@@ -31,8 +47,14 @@ export default function BatchView({ filePaths, onClose }: BatchViewProps) {
       <div className="flex flex-col items-center justify-center h-screen gap-3">
         <div className="w-10 h-10 border-3 border-sky-400 border-t-transparent rounded-full animate-spin" />
         <div className="text-slate-400 text-sm">
-          {filePaths.length} 件のファイルを処理中...
+          {doneCount} / {filePaths.length} 件のファイルを処理中...
         </div>
+        <button
+          onClick={() => cancelBatch()}
+          className="px-4 py-2 rounded bg-slate-700 hover:bg-slate-600 text-sm transition-colors"
+        >
+          中止
+        </button>
       </div>
     );
   }
@@ -54,6 +76,23 @@ export default function BatchView({ filePaths, onClose }: BatchViewProps) {
 
   if (!result) return null;
 
+  async function handleExportReport(report: BatchResult) {
+    try {
+      const outPath = await save({
+        defaultPath: "batch-report.csv",
+        filters: [
+          { name: "CSV", extensions: ["csv"] },
+          { name: "JSON", extensions: ["json"] },
+        ],
+      });
+      if (!outPath) return;
+      const format = outPath.toLowerCase().endsWith(".json") ? "json" : "csv";
+      await exportBatchReport(report, format, outPath);
+    } catch (e) {
+      setError(String(e));
+    }
+  }
+
   const statusIcon = (status: string) => {
     switch (status) {
       case "converted":
@@ -62,6 +101,8 @@ export default function BatchView({ filePaths, onClose }: BatchViewProps) {
         return <span className="text-blue-400">&#10003;</span>;
       case "binary":
         return <span className="text-slate-400">&#9654;</span>;
+      case "skipped":
+        return <span className="text-yellow-400">&#8722;</span>;
       case "error":
         return <span className="text-red-400">&#10007;</span>;
       default:
@@ -77,6 +118,8 @@ export default function BatchView({ filePaths, onClose }: BatchViewProps) {
         return "UTF-8";
       case "binary":
         return "バイナリ";
+      case "skipped":
+        return "スキップ";
       case "error":
         return "エラー";
       default:
@@ -92,6 +135,7 @@ export default function BatchView({ filePaths, onClose }: BatchViewProps) {
           バッチ処理結果
         </h1>
         <span className="text-sm text-slate-400">
+          {result.cancelled && "中止 - "}
           {result.total} 件
         </span>
       </div>
@@ -113,11 +157,21 @@ export default function BatchView({ filePaths, onClose }: BatchViewProps) {
             バイナリ: {result.binary}
           </span>
         )}
+        {result.skipped > 0 && (
+          <span className="px-2 py-1 rounded bg-yellow-900/50 text-yellow-300">
+            スキップ: {result.skipped}
+          </span>
+        )}
         {result.errors > 0 && (
           <span className="px-2 py-1 rounded bg-red-900/50 text-red-300">
             エラー: {result.errors}
           </span>
         )}
+        {result.duplicates_skipped > 0 && (
+          <span className="px-2 py-1 rounded bg-slate-700 text-slate-300">
+            重複: {result.duplicates_skipped}
+          </span>
+        )}
       </div>
 
       {/* File list */}
@@ -137,6 +191,11 @@ export default function BatchView({ filePaths, onClose }: BatchViewProps) {
                   {file.detected_encoding} &rarr; UTF-8 BOM
                 </div>
               )}
+              {file.lossy && (
+                <div className="text-xs text-yellow-400">
+                  {file.replacement_count} 文字を変換できませんでした
+                </div>
+              )}
               {file.error_message && (
                 <div className="text-xs text-red-400">{file.error_message}</div>
               )}
@@ -149,12 +208,20 @@ export default function BatchView({ filePaths, onClose }: BatchViewProps) {
       </div>
 
       {/* Actions */}
-      <button
-        onClick={onClose}
-        className="self-center px-6 py-2 rounded bg-sky-600 hover:bg-sky-500 text-sm font-medium transition-colors"
-      >
-        閉じる
-      </button>
+      <div className="self-center flex gap-2">
+        <button
+          onClick={() => handleExportReport(result)}
+          className="px-4 py-2 rounded bg-slate-700 hover:bg-slate-600 text-sm transition-colors"
+        >
+          レポートを保存
+        </button>
+        <button
+          onClick={onClose}
+          className="px-6 py-2 rounded bg-sky-600 hover:bg-sky-500 text-sm font-medium transition-colors"
+        >
+          閉じる
+        </button>
+      </div>
     </div>
   );
 }
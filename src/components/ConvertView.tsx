@@ -1,9 +1,11 @@
 import { useEffect, useState } from "react";
-import type { ConvertResult } from "../lib/tauri-commands";
+import type { ConvertResult, EncodingInfo } from "../lib/tauri-commands";
 import {
+  convertTo,
   convertWithEncoding,
-  getSupportedEncodings,
+  getSupportedEncodingsDetailed,
   openConvertedFile,
+  setEncodingOverride,
 } from "../lib/tauri-commands";
 
 interface ConvertViewProps {
@@ -15,12 +17,14 @@ export default function ConvertView({ result, onClose }: ConvertViewProps) {
   const [selectedEncoding, setSelectedEncoding] = useState(
     result.detected_encoding
   );
-  const [encodings, setEncodings] = useState<string[]>([]);
+  const [encodings, setEncodings] = useState<EncodingInfo[]>([]);
   const [converting, setConverting] = useState(false);
+  const [remember, setRemember] = useState(false);
+  const [exportEncoding, setExportEncoding] = useState("Shift_JIS");
   const [error, setError] = useState<string | null>(null);
 
   useEffect(() => {
-    getSupportedEncodings().then(setEncodings);
+    getSupportedEncodingsDetailed().then(setEncodings);
   }, []);
 
   const confidencePercent = Math.round(result.confidence * 100);
@@ -35,10 +39,31 @@ export default function ConvertView({ result, onClose }: ConvertViewProps) {
     setConverting(true);
     setError(null);
     try {
-      const tempPath = await convertWithEncoding(
+      if (remember) {
+        await setEncodingOverride(result.original_path, selectedEncoding);
+      }
+      const opened = await convertWithEncoding(
         result.original_path,
         selectedEncoding
       );
+      if (opened.launch_error) {
+        setError(`変換しましたが開けませんでした: ${opened.launch_error}`);
+        return;
+      }
+      await openConvertedFile(opened.temp_path);
+      onClose();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setConverting(false);
+    }
+  }
+
+  async function handleExport() {
+    setConverting(true);
+    setError(null);
+    try {
+      const tempPath = await convertTo(result.original_path, exportEncoding);
       await openConvertedFile(tempPath);
       onClose();
     } catch (e) {
@@ -75,6 +100,12 @@ export default function ConvertView({ result, onClose }: ConvertViewProps) {
           </div>
         </div>
       </div>
+      <div className="text-xs text-slate-400">{result.explanation}</div>
+      {result.mixed_encoding_warning && (
+        <div className="bg-yellow-900/50 border border-yellow-700 rounded p-2 text-xs text-yellow-300">
+          警告: ファイル内に複数のエンコードが混在している可能性があります。一部が文字化けすることがあります
+        </div>
+      )}
 
       {/* Encoding Selector */}
       <div className="flex items-center gap-3">
@@ -87,9 +118,9 @@ export default function ConvertView({ result, onClose }: ConvertViewProps) {
           onChange={(e) => setSelectedEncoding(e.target.value)}
         >
           {encodings.map((enc) => (
-            <option key={enc} value={enc}>
-              {enc}
-              {enc === result.detected_encoding ? " (検出)" : ""}
+            <option key={enc.label} value={enc.label}>
+              {enc.display_name}
+              {enc.label === result.detected_encoding ? " (検出)" : ""}
             </option>
           ))}
         </select>
@@ -123,6 +154,31 @@ export default function ConvertView({ result, onClose }: ConvertViewProps) {
         </div>
       </div>
 
+      {/* Export in another encoding */}
+      <div className="flex items-center gap-3">
+        <label className="text-sm text-slate-400 whitespace-nowrap">
+          出力エンコード:
+        </label>
+        <select
+          className="flex-1 bg-slate-700 border border-slate-600 rounded px-3 py-1.5 text-sm focus:outline-none focus:border-sky-400"
+          value={exportEncoding}
+          onChange={(e) => setExportEncoding(e.target.value)}
+        >
+          {encodings.map((enc) => (
+            <option key={enc.label} value={enc.label}>
+              {enc.display_name}
+            </option>
+          ))}
+        </select>
+        <button
+          onClick={handleExport}
+          disabled={converting}
+          className="px-4 py-1.5 rounded bg-slate-700 hover:bg-slate-600 text-sm transition-colors disabled:opacity-50"
+        >
+          このエンコードで書き出す
+        </button>
+      </div>
+
       {/* Error */}
       {error && (
         <div className="bg-red-900/50 border border-red-700 rounded p-2 text-sm text-red-300">
@@ -131,7 +187,15 @@ export default function ConvertView({ result, onClose }: ConvertViewProps) {
       )}
 
       {/* Actions */}
-      <div className="flex justify-end gap-2">
+      <div className="flex justify-end items-center gap-2">
+        <label className="flex items-center gap-2 text-xs text-slate-400 mr-auto">
+          <input
+            type="checkbox"
+            checked={remember}
+            onChange={(e) => setRemember(e.target.checked)}
+          />
+          このファイルは次回からこのエンコードで開く
+        </label>
         <button
           onClick={onClose}
           className="px-4 py-2 rounded bg-slate-700 hover:bg-slate-600 text-sm transition-colors"
@@ -1,6 +1,6 @@
 import { useEffect, useState } from "react";
 import type { AppConfig } from "../lib/tauri-commands";
-import { getConfig, updateConfig } from "../lib/tauri-commands";
+import { getConfig, takeConfigWarning, updateConfig } from "../lib/tauri-commands";
 import { open } from "@tauri-apps/plugin-dialog";
 import { check } from "@tauri-apps/plugin-updater";
 import { relaunch } from "@tauri-apps/plugin-process";
@@ -27,7 +27,12 @@ export default function Settings({ onBack }: SettingsProps) {
   const [appVersion, setAppVersion] = useState("");
 
   useEffect(() => {
-    getConfig().then(setConfig);
+    getConfig()
+      .then(setConfig)
+      .then(() => takeConfigWarning())
+      .then((warning) => {
+        if (warning) setMessage(`警告: ${warning}`);
+      });
     getVersion().then(setAppVersion);
   }, []);
 
@@ -178,6 +183,45 @@ export default function Settings({ onBack }: SettingsProps) {
           </select>
         </div>
 
+        {/* Strip Existing BOM */}
+        {config.target_encoding === "utf-8" && (
+          <div className="flex items-center gap-3">
+            <input
+              type="checkbox"
+              id="stripExistingBom"
+              checked={config.strip_existing_bom}
+              onChange={(e) =>
+                setConfig({ ...config, strip_existing_bom: e.target.checked })
+              }
+              className="w-4 h-4 accent-sky-400"
+            />
+            <label htmlFor="stripExistingBom" className="text-sm text-slate-300">
+              BOM付き UTF-8 ファイルも BOM を取り除いて開く
+            </label>
+          </div>
+        )}
+
+        {/* Line Ending */}
+        <div className="flex flex-col gap-1.5">
+          <label className="text-sm font-medium text-slate-300">
+            改行コード
+          </label>
+          <select
+            value={config.line_ending}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                line_ending: e.target.value as AppConfig["line_ending"],
+              })
+            }
+            className="bg-slate-800 border border-slate-600 rounded px-3 py-1.5 text-sm focus:outline-none focus:border-sky-400"
+          >
+            <option value="preserve">元のまま (推奨)</option>
+            <option value="lf">LF</option>
+            <option value="crlf">CRLF</option>
+          </select>
+        </div>
+
         {/* Smart Auto-Fix Info */}
         <div className="flex flex-col gap-1.5">
           <label className="text-sm font-medium text-slate-300">
@@ -208,6 +252,77 @@ export default function Settings({ onBack }: SettingsProps) {
           />
         </div>
 
+        {/* Write Mode */}
+        <div className="flex flex-col gap-1.5">
+          <label className="text-sm font-medium text-slate-300">
+            変換結果の保存先
+          </label>
+          <select
+            value={config.write_mode}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                write_mode: e.target.value as AppConfig["write_mode"],
+              })
+            }
+            className="bg-slate-800 border border-slate-600 rounded px-3 py-1.5 text-sm focus:outline-none focus:border-sky-400"
+          >
+            <option value="temp">一時ファイル (推奨)</option>
+            <option value="in_place">元のファイルを上書き</option>
+          </select>
+        </div>
+
+        {/* Backup Before Overwrite */}
+        {config.write_mode === "in_place" && (
+          <div className="flex items-center gap-3">
+            <input
+              type="checkbox"
+              id="backupBeforeOverwrite"
+              checked={config.backup_before_overwrite}
+              onChange={(e) =>
+                setConfig({
+                  ...config,
+                  backup_before_overwrite: e.target.checked,
+                })
+              }
+              className="w-4 h-4 accent-sky-400"
+            />
+            <label
+              htmlFor="backupBeforeOverwrite"
+              className="text-sm text-slate-300"
+            >
+              上書き前に .bak ファイルを作成する
+            </label>
+            {!config.backup_before_overwrite && (
+              <span className="text-xs text-slate-500">
+                Git リポジトリ内のファイルは上書きされません
+              </span>
+            )}
+          </div>
+        )}
+
+        {/* Batch Launch Mode */}
+        <div className="flex flex-col gap-1.5">
+          <label className="text-sm font-medium text-slate-300">
+            一括変換後に開くファイル
+          </label>
+          <select
+            value={config.batch_launch_mode}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                batch_launch_mode: e.target
+                  .value as AppConfig["batch_launch_mode"],
+              })
+            }
+            className="bg-slate-800 border border-slate-600 rounded px-3 py-1.5 text-sm focus:outline-none focus:border-sky-400"
+          >
+            <option value="all">すべて開く</option>
+            <option value="first_only">最初の 1 件だけ開く</option>
+            <option value="none">開かない</option>
+          </select>
+        </div>
+
         {/* Keep Temp File */}
         <div className="flex items-center gap-3">
           <input
@@ -224,6 +339,54 @@ export default function Settings({ onBack }: SettingsProps) {
           </label>
         </div>
 
+        {/* Strict Conversion */}
+        <div className="flex items-center gap-3">
+          <input
+            type="checkbox"
+            id="strictConvert"
+            checked={!config.lossy_allowed}
+            onChange={(e) =>
+              setConfig({ ...config, lossy_allowed: !e.target.checked })
+            }
+            className="w-4 h-4 accent-sky-400"
+          />
+          <label htmlFor="strictConvert" className="text-sm text-slate-300">
+            変換できない文字がある場合はエラーにする
+          </label>
+        </div>
+
+        {/* Round-Trip Verification */}
+        <div className="flex items-center gap-3">
+          <input
+            type="checkbox"
+            id="verifyRoundTrip"
+            checked={config.verify_round_trip}
+            onChange={(e) =>
+              setConfig({ ...config, verify_round_trip: e.target.checked })
+            }
+            className="w-4 h-4 accent-sky-400"
+          />
+          <label htmlFor="verifyRoundTrip" className="text-sm text-slate-300">
+            変換後に元のエンコーディングへ戻して一致を確認する
+          </label>
+        </div>
+
+        {/* Respect .gitignore */}
+        <div className="flex items-center gap-3">
+          <input
+            type="checkbox"
+            id="respectGitignore"
+            checked={config.respect_gitignore}
+            onChange={(e) =>
+              setConfig({ ...config, respect_gitignore: e.target.checked })
+            }
+            className="w-4 h-4 accent-sky-400"
+          />
+          <label htmlFor="respectGitignore" className="text-sm text-slate-300">
+            フォルダ走査で .gitignore の対象を除外する
+          </label>
+        </div>
+
         {/* Update Section */}
         <div className="flex flex-col gap-2 border-t border-slate-700 pt-4">
           <label className="text-sm font-medium text-slate-300">
@@ -319,7 +482,7 @@ export default function Settings({ onBack }: SettingsProps) {
       {message && (
         <div
           className={`rounded p-2 text-sm ${
-            message.startsWith("エラー")
+            message.startsWith("エラー") || message.startsWith("警告")
               ? "bg-red-900/50 border border-red-700 text-red-300"
               : "bg-green-900/50 border border-green-700 text-green-300"
           }`}
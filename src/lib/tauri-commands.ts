@@ -10,22 +10,82 @@ export interface ConvertResult {
   converted_preview: string[];
   original_path: string;
   file_name: string;
+  replacement_count: number;
+  lossy: boolean;
+  round_trip_ok: boolean | null;
+  line_ending: "lf" | "crlf" | "cr" | "mixed" | "none";
+  detected_delimiter: "," | ";" | "\\t" | "|" | null;
+  explanation: string;
+  preview_diff: [string | null, string | null][];
+  mixed_encoding_warning: boolean;
+  launch_error: string | null;
+  double_encoding_preview: string[];
+}
+
+export interface ConvertProgress {
+  file_path: string;
+  bytes_processed: number;
+  total_bytes: number;
+}
+
+export interface ScoringWeights {
+  replacement: number;
+  valid: number;
+  cjk: number;
+  letters: number;
+  base: number;
+  error_penalty: number;
 }
 
 export interface AppConfig {
+  version: number;
   default_app: string;
   target_encoding: string;
   confidence_threshold: number;
   preview_lines: number;
+  preview_max_width: number;
   keep_temp_file: boolean;
+  reuse_temp: boolean;
+  write_mode: "temp" | "in_place";
+  backup_before_overwrite: boolean;
+  batch_launch_mode: "all" | "none" | "first_only";
+  line_ending: "preserve" | "lf" | "crlf";
+  trim_trailing_whitespace: boolean;
+  ensure_final_newline: boolean | null;
+  strip_existing_bom: boolean;
+  candidate_encodings: string[];
+  scoring_weights: ScoringWeights;
+  lossy_allowed: boolean;
+  verify_round_trip: boolean;
+  log_level: "off" | "error" | "warn" | "info" | "debug" | "trace";
+  max_scan_depth: number;
+  respect_gitignore: boolean;
+  max_file_size_bytes: number | null;
+  temp_dir: string | null;
+  app_by_extension: Record<string, string>;
+  launch_args: string[];
+  launch_retries: number;
+  launch_retry_delay_ms: number;
+  encoding_overrides: Record<string, string>;
+  chunked_threshold_mb: number;
 }
 
 export interface BatchFileResult {
   file_path: string;
   file_name: string;
-  status: "converted" | "already_utf8" | "binary" | "error";
+  status: "converted" | "already_utf8" | "binary" | "skipped" | "error";
   detected_encoding: string | null;
   error_message: string | null;
+  replacement_count: number;
+  lossy: boolean;
+  converted_path: string | null;
+}
+
+export interface BatchProgress {
+  index: number;
+  total: number;
+  file_name: string;
+  status: BatchFileResult["status"];
 }
 
 export interface BatchResult {
@@ -34,7 +94,25 @@ export interface BatchResult {
   converted: number;
   already_utf8: number;
   binary: number;
+  skipped: number;
   errors: number;
+  cancelled: boolean;
+  duplicates_skipped: number;
+}
+
+export interface EncodingScore {
+  encoding_name: string;
+  score: number;
+  replacement_count: number;
+  cjk_char_count: number;
+  total_chars: number;
+  structural_errors: number;
+}
+
+export interface AnalysisReport {
+  detected_encoding: string;
+  confidence: number;
+  scores: EncodingScore[];
 }
 
 export async function detectAndConvert(
@@ -43,37 +121,161 @@ export async function detectAndConvert(
   return invoke("detect_and_convert", { filePath });
 }
 
+export interface OpenedFile {
+  temp_path: string;
+  launch_error: string | null;
+}
+
 export async function convertWithEncoding(
   filePath: string,
   encoding: string
-): Promise<string> {
+): Promise<OpenedFile> {
   return invoke("convert_with_encoding", { filePath, encoding });
 }
 
+export async function reconvert(
+  originalPath: string,
+  encoding: string
+): Promise<OpenedFile> {
+  return invoke("reconvert", { originalPath, encoding });
+}
+
 export async function batchConvert(
-  filePaths: string[]
+  filePaths: string[],
+  dryRun = false
+): Promise<BatchResult> {
+  return invoke("batch_convert", { filePaths, dryRun });
+}
+
+export async function batchConvertToZip(
+  filePaths: string[],
+  zipPath: string
+): Promise<BatchResult> {
+  return invoke("batch_convert_to_zip", { filePaths, zipPath });
+}
+
+export async function exportBatchReport(
+  result: BatchResult,
+  format: "csv" | "json",
+  outPath: string
+): Promise<void> {
+  return invoke("export_batch_report", { result, format, outPath });
+}
+
+export async function convertFolder(
+  folderPath: string,
+  extensions?: string[],
+  excludeGlobs?: string[]
 ): Promise<BatchResult> {
-  return invoke("batch_convert", { filePaths });
+  return invoke("convert_folder", {
+    folderPath,
+    extensions: extensions ?? null,
+    excludeGlobs: excludeGlobs ?? null,
+  });
+}
+
+export async function convertFolderTo(
+  folderPath: string,
+  outputDir: string,
+  extensions?: string[],
+  excludeGlobs?: string[]
+): Promise<BatchResult> {
+  return invoke("convert_folder_to", {
+    folderPath,
+    outputDir,
+    extensions: extensions ?? null,
+    excludeGlobs: excludeGlobs ?? null,
+  });
+}
+
+export async function startWatching(folderPath: string): Promise<void> {
+  return invoke("start_watching", { folderPath });
+}
+
+export async function stopWatching(): Promise<void> {
+  return invoke("stop_watching");
+}
+
+export async function cancelBatch(): Promise<void> {
+  return invoke("cancel_batch");
 }
 
 export async function scanFolder(
-  folderPath: string
+  folderPath: string,
+  extensions?: string[],
+  excludeGlobs?: string[]
+): Promise<string[]> {
+  return invoke("scan_folder", {
+    folderPath,
+    extensions: extensions ?? null,
+    excludeGlobs: excludeGlobs ?? null,
+  });
+}
+
+export async function analyzeFile(
+  filePath: string
+): Promise<AnalysisReport> {
+  return invoke("analyze_file", { filePath });
+}
+
+export async function previewFile(
+  filePath: string,
+  encoding: string,
+  lines: number
 ): Promise<string[]> {
-  return invoke("scan_folder", { folderPath });
+  return invoke("preview_file", { filePath, encoding, lines });
+}
+
+export async function convertClipboard(): Promise<string> {
+  return invoke("convert_clipboard");
 }
 
 export async function getConfig(): Promise<AppConfig> {
   return invoke("get_config");
 }
 
+export async function takeConfigWarning(): Promise<string | null> {
+  return invoke("take_config_warning");
+}
+
 export async function updateConfig(newConfig: AppConfig): Promise<void> {
   return invoke("update_config", { newConfig });
 }
 
+export async function clearDetectionCache(): Promise<void> {
+  return invoke("clear_detection_cache");
+}
+
+export async function setEncodingOverride(
+  path: string,
+  encoding: string
+): Promise<void> {
+  return invoke("set_encoding_override", { path, encoding });
+}
+
 export async function getSupportedEncodings(): Promise<string[]> {
   return invoke("get_supported_encodings");
 }
 
+export interface EncodingInfo {
+  label: string;
+  display_name: string;
+  aliases: string[];
+  language: string;
+}
+
+export async function getSupportedEncodingsDetailed(): Promise<EncodingInfo[]> {
+  return invoke("get_supported_encodings_detailed");
+}
+
+export async function convertTo(filePath: string, targetEncoding: string): Promise<string> {
+  return invoke("convert_to", { filePath, targetEncoding });
+}
+
+export async function recoverDoubleEncoding(filePath: string): Promise<OpenedFile> {
+  return invoke("recover_double_encoding", { filePath });
+}
+
 export async function openConvertedFile(tempPath: string): Promise<void> {
   return invoke("open_converted_file", { tempPath });
 }
@@ -81,3 +283,7 @@ export async function openConvertedFile(tempPath: string): Promise<void> {
 export async function cleanupTemp(tempPath: string): Promise<void> {
   return invoke("cleanup_temp", { tempPath });
 }
+
+export async function cleanupAllTemp(olderThanSecs?: number): Promise<number> {
+  return invoke("cleanup_all_temp", { olderThanSecs: olderThanSecs ?? null });
+}